@@ -1,6 +1,15 @@
-use crate::commands::{build_cmd, build_command};
+use crate::commands::{
+    add_cmd, add_command, bench_cmd, bench_command, build_cmd, build_command, check_cmd,
+    check_command, clean_cmd, clean_command, doc_cmd, doc_command, external_command, graph_cmd,
+    graph_command, init_cmd, init_command, list_commands, metadata_cmd, metadata_command, new_cmd,
+    new_command, remove_cmd, remove_command, run_cmd, run_command, test_cmd, test_command,
+    tree_cmd, tree_command, why_cmd, why_command,
+};
 use clap::{Arg, ArgAction, Command, builder::Styles};
 use kelpie_core::zirael_core::prelude::*;
+use kelpie_core::{Aliases, KelpieConfig, PackageSelection};
+use std::env;
+use std::ffi::OsString;
 
 pub fn opt(name: &'static str, help: &'static str) -> Arg {
     Arg::new(name).long(name).help(help).action(ArgAction::Set)
@@ -19,6 +28,39 @@ pub fn package_arg() -> Arg {
         .action(ArgAction::Set)
 }
 
+/// `-p` accepting names, glob patterns and package ID specs, together with `--workspace` and
+/// `--exclude`. Read back with [`package_selection`].
+pub fn package_selection_args(cmd: Command, help: &'static str) -> Command {
+    cmd.arg(opt("package", help).short('p').action(ArgAction::Append))
+        .arg(
+            opt("workspace", "Select every package in the workspace")
+                .visible_alias("all")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            opt("exclude", "Leave a package out of --workspace")
+                .action(ArgAction::Append)
+                .requires("workspace"),
+        )
+}
+
+pub fn package_selection(cli_args: &clap::ArgMatches) -> PackageSelection {
+    let values = |name: &str| -> Vec<String> {
+        cli_args
+            .get_many::<String>(name)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    };
+
+    PackageSelection {
+        workspace: cli_args.get_flag("workspace"),
+        packages: values("package"),
+        exclude: values("exclude"),
+    }
+}
+
 pub fn release_mode() -> Arg {
     opt("release", "Build in release mode")
         .action(ArgAction::SetTrue)
@@ -36,21 +78,55 @@ pub fn debug_mode() -> Arg {
 }
 
 pub fn dynamic_lib_mode() -> Arg {
-    opt("dynamic", "Build a dynamic library")
-        .action(ArgAction::SetTrue)
-        .short('l')
-        .conflicts_with("static")
-        .help_heading(COMPILATION_HEADING)
+    opt(
+        "dynamic",
+        "Build a dynamic library, overriding the manifest's lib-type",
+    )
+    .action(ArgAction::SetTrue)
+    .short('l')
+    .conflicts_with("static")
+    .help_heading(COMPILATION_HEADING)
 }
 
 pub fn static_lib_mode() -> Arg {
-    opt("static", "Build a static library")
-        .action(ArgAction::SetTrue)
-        .short('s')
-        .conflicts_with("dynamic")
+    opt(
+        "static",
+        "Build a static library, overriding the manifest's lib-type",
+    )
+    .action(ArgAction::SetTrue)
+    .short('s')
+    .conflicts_with("dynamic")
+    .help_heading(COMPILATION_HEADING)
+}
+
+pub fn target_triple_arg() -> Arg {
+    opt("target", "Cross-compile for the given target triple").help_heading(COMPILATION_HEADING)
+}
+
+pub fn target_dir_arg() -> Arg {
+    opt("target-dir", "Directory for all generated artifacts")
+        .value_parser(clap::value_parser!(std::path::PathBuf))
         .help_heading(COMPILATION_HEADING)
 }
 
+/// The kelpie configuration for the current directory, with the `--config` overrides.
+pub fn load_config(cli_args: &clap::ArgMatches) -> anyhow::Result<KelpieConfig> {
+    let overrides: Vec<String> = cli_args
+        .try_get_many::<String>("config")
+        .ok()
+        .flatten()
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    KelpieConfig::load(&env::current_dir()?, &overrides)
+}
+
+/// Whether `-v` was given or `term.verbose` is set in the configuration.
+pub fn verbose(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> bool {
+    cli_args.get_flag("verbose") || config.term.verbose == Some(true)
+}
+
 pub fn cli() -> Command {
     let styles = {
         Styles::styled()
@@ -90,21 +166,91 @@ pub fn cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .global(true),
         )
+        .arg(
+            opt(
+                "config",
+                "Override a kelpie configuration value, can be repeated",
+            )
+            .value_name("KEY=VALUE")
+            .action(ArgAction::Append)
+            .global(true),
+        )
+        .arg(
+            opt("list", "List the built-in commands and installed plugins")
+                .action(ArgAction::SetTrue),
+        )
         .subcommand(build_cmd())
+        .subcommand(check_cmd())
+        .subcommand(clean_cmd())
+        .subcommand(add_cmd())
+        .subcommand(remove_cmd())
+        .subcommand(new_cmd())
+        .subcommand(init_cmd())
+        .subcommand(run_cmd())
+        .subcommand(test_cmd())
+        .subcommand(bench_cmd())
+        .subcommand(doc_cmd())
+        .subcommand(tree_cmd())
+        .subcommand(metadata_cmd())
+        .subcommand(graph_cmd())
+        .subcommand(why_cmd())
 }
 
 pub fn try_cli() -> Result<()> {
     let mut cli = cli();
-    let matches = cli.clone().get_matches();
-    setup_logger(matches.get_flag("verbose"), matches.get_flag("test-logger"));
+    let args: Vec<OsString> = env::args_os().collect();
+    let mut matches = cli.clone().get_matches_from(&args);
+
+    // the logger is needed to report a broken configuration
+    let config = load_config(&matches);
+    let default = KelpieConfig::default();
+    let logged = config.as_ref().unwrap_or(&default);
+    setup_logger(verbose(&matches, logged), matches.get_flag("test-logger"));
+    if logged.term.quiet == Some(true) && !matches.get_flag("verbose") {
+        log::set_max_level(log::LevelFilter::Warn);
+    }
+    let mut config = config?;
+
+    // aliases look like external commands to clap, they're expanded once logging works
+    let aliases = Aliases::new(&config);
+    let builtins: Vec<&str> = cli
+        .get_subcommands()
+        .flat_map(|cmd| std::iter::once(cmd.get_name()).chain(cmd.get_all_aliases()))
+        .chain(["help"])
+        .collect();
+    aliases.check(&builtins)?;
+    if matches
+        .subcommand_name()
+        .is_some_and(|name| aliases.get(name).is_some())
+    {
+        matches = cli.clone().get_matches_from(aliases.expand(args)?);
+        // the alias can come with `--config` overrides of its own
+        config = load_config(&matches)?;
+    }
+
+    if matches.get_flag("list") {
+        list_commands(&cli, &aliases);
+        return Ok(());
+    }
 
     if let Some((cmd, args)) = matches.subcommand() {
         match cmd {
-            "build" => build_command(args),
-            _ => {
-                cli.print_help()?;
-                Ok(())
-            }
+            "build" => build_command(args, &config),
+            "check" => check_command(args, &config),
+            "clean" => clean_command(args, &config),
+            "add" => add_command(args),
+            "remove" => remove_command(args),
+            "new" => new_command(args),
+            "init" => init_command(args),
+            "run" => run_command(args, &config),
+            "test" => test_command(args, &config),
+            "bench" => bench_command(args, &config),
+            "doc" => doc_command(args, &config),
+            "tree" => tree_command(args),
+            "metadata" => metadata_command(args, &config),
+            "graph" => graph_command(args),
+            "why" => why_command(args),
+            _ => external_command(cmd, args),
         }
     } else {
         cli.print_help()?;
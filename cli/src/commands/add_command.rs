@@ -0,0 +1,233 @@
+use crate::cli::{opt, package_arg, positional};
+use crate::commands::edit::{find_edit_target, verify_and_save};
+use anyhow::{Result, anyhow, bail};
+use clap::ArgAction;
+use kelpie_core::zirael_core::prelude::canonicalize_with_strip;
+use kelpie_core::{
+    CONFIG_FILE, DependencyKind, DetailedDependency, GitReference, ManifestEditor, TomlConfig,
+    TomlDependency, checkout_git_dependency, manifest_path_string, relative_path,
+};
+use log::info;
+use std::env::current_dir;
+use std::path::Path;
+
+pub const SOURCE_HEADING: &str = "Source options";
+
+pub fn dependency_kind_args(cmd: clap::Command) -> clap::Command {
+    cmd.arg(
+        opt("dev", "Use [dev-dependencies]")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("build"),
+    )
+    .arg(
+        opt("build", "Use [build-dependencies]")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("dev"),
+    )
+}
+
+pub fn dependency_kind(cli_args: &clap::ArgMatches) -> DependencyKind {
+    if cli_args.get_flag("dev") {
+        DependencyKind::Development
+    } else if cli_args.get_flag("build") {
+        DependencyKind::Build
+    } else {
+        DependencyKind::Normal
+    }
+}
+
+pub fn add_cmd() -> clap::Command {
+    let cmd = clap::Command::new("add")
+        .about("Add a dependency to a manifest")
+        .arg(positional(
+            "dependency",
+            "Dependency to add, optionally with a version requirement (`foo@^1.2`)",
+        ))
+        .arg(opt("path", "Local directory of the dependency").help_heading(SOURCE_HEADING))
+        .arg(
+            opt("git", "Git repository of the dependency")
+                .conflicts_with("path")
+                .help_heading(SOURCE_HEADING),
+        )
+        .arg(
+            opt("tag", "Git tag to check out")
+                .requires("git")
+                .conflicts_with_all(["branch", "rev"])
+                .help_heading(SOURCE_HEADING),
+        )
+        .arg(
+            opt("branch", "Git branch to check out")
+                .requires("git")
+                .conflicts_with("rev")
+                .help_heading(SOURCE_HEADING),
+        )
+        .arg(
+            opt("rev", "Git revision to check out")
+                .requires("git")
+                .help_heading(SOURCE_HEADING),
+        )
+        .arg(
+            opt(
+                "features",
+                "Features to enable, separated by commas or spaces",
+            )
+            .short('F')
+            .action(ArgAction::Append),
+        )
+        .arg(package_arg().help("Package to add the dependency to"))
+        .arg(
+            opt(
+                "to-workspace",
+                "Add the dependency to [workspace.dependencies] and reference it from the package",
+            )
+            .action(ArgAction::SetTrue),
+        );
+
+    dependency_kind_args(cmd)
+}
+
+fn parse_dependency_spec(spec: &str) -> (String, Option<String>) {
+    match spec.split_once('@') {
+        Some((name, version)) => (name.to_string(), Some(version.to_string())),
+        None => (spec.to_string(), None),
+    }
+}
+
+fn read_package(root: &Path) -> Result<(String, String)> {
+    let manifest_path = root.join(CONFIG_FILE);
+    let package = TomlConfig::from_path(&manifest_path)?
+        .package
+        .ok_or_else(|| anyhow!("{} doesn't define a package", manifest_path.display()))?;
+    Ok((package.name, package.version))
+}
+
+pub fn add_command(cli_args: &clap::ArgMatches) -> Result<()> {
+    let kind = dependency_kind(cli_args);
+    let to_workspace = cli_args.get_flag("to-workspace");
+    let target = find_edit_target(cli_args.get_one::<String>("package"))?;
+
+    if !target.is_workspace && to_workspace {
+        bail!("--to-workspace can only be used inside a workspace");
+    }
+    if target.package_manifest.is_none() {
+        if !to_workspace {
+            bail!(
+                "Cannot add a dependency in a workspace without specifying a package (use -p <package-name> or --to-workspace)"
+            );
+        }
+        if kind != DependencyKind::Normal {
+            bail!("[workspace.dependencies] can't contain dev or build dependencies");
+        }
+    }
+
+    let (mut name, mut version) = match cli_args.get_one::<String>("dependency") {
+        Some(spec) => {
+            let (name, version) = parse_dependency_spec(spec);
+            (Some(name), version)
+        }
+        None => (None, None),
+    };
+
+    let features: Vec<String> = cli_args
+        .get_many::<String>("features")
+        .into_iter()
+        .flatten()
+        .flat_map(|features| features.split([',', ' ']))
+        .filter(|feature| !feature.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    // the manifest whose directory relative paths are written against
+    let source_manifest = if to_workspace {
+        &target.root_manifest
+    } else {
+        target.package_manifest.as_ref().unwrap()
+    };
+    let source_dir = source_manifest.parent().unwrap();
+
+    let mut dep = DetailedDependency::default();
+    if let Some(path) = cli_args.get_one::<String>("path") {
+        let dep_root = canonicalize_with_strip(current_dir()?.join(path))
+            .map_err(|_| anyhow!("path {} doesn't exist", path))?;
+        let (package_name, package_version) = read_package(&dep_root)?;
+
+        name.get_or_insert(package_name);
+        version.get_or_insert(package_version);
+        dep.path = Some(manifest_path_string(&relative_path(source_dir, &dep_root)));
+    } else if let Some(url) = cli_args.get_one::<String>("git") {
+        let Some(name) = &name else {
+            bail!("the name of a git dependency has to be specified");
+        };
+
+        dep.git = Some(url.clone());
+        dep.tag = cli_args.get_one::<String>("tag").cloned();
+        dep.branch = cli_args.get_one::<String>("branch").cloned();
+        dep.rev = cli_args.get_one::<String>("rev").cloned();
+
+        if version.is_none() {
+            let checkout =
+                checkout_git_dependency(name, url, GitReference::from_dependency(&dep)?)?;
+            version = Some(read_package(&checkout)?.1);
+        }
+    }
+
+    let Some(name) = name else {
+        bail!("no dependency specified");
+    };
+    let Some(version) = version else {
+        bail!(
+            "no version specified for dependency {} (use {}@<version>)",
+            name,
+            name
+        );
+    };
+
+    // when referencing a workspace dependency from a package, the features go on the reference
+    let features_on_reference = to_workspace && target.package_manifest.is_some();
+    let spec_features = !features.is_empty() && !features_on_reference;
+
+    dep.version = Some(version.clone());
+    let spec = if dep.path.is_none() && dep.git.is_none() && !spec_features {
+        TomlDependency::Version(version.clone())
+    } else {
+        if spec_features {
+            dep.features = Some(features.clone());
+        }
+        TomlDependency::Detailed(dep)
+    };
+
+    let mut editors = vec![];
+    if to_workspace {
+        let mut editor = ManifestEditor::open(&target.root_manifest)?;
+        editor.add_workspace_dependency(&name, &spec);
+        editors.push(editor);
+    }
+    if let Some(package_manifest) = &target.package_manifest {
+        let mut editor = ManifestEditor::open(package_manifest)?;
+        if to_workspace {
+            let reference = DetailedDependency {
+                workspace: Some(true),
+                features: (!features.is_empty()).then(|| features.clone()),
+                ..DetailedDependency::default()
+            };
+            editor.add_dependency(kind, &name, &TomlDependency::Detailed(reference));
+        } else {
+            editor.add_dependency(kind, &name, &spec);
+        }
+        editors.push(editor);
+    }
+
+    verify_and_save(&target.root_manifest, &editors.iter().collect::<Vec<_>>())?;
+
+    let table = if target.package_manifest.is_none() {
+        "workspace.dependencies"
+    } else {
+        kind.table_name()
+    };
+    info!("Added {} v{} to [{}]", name, version, table);
+    if !features.is_empty() {
+        info!("Features: {}", features.join(", "));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use crate::cli::{opt, package_selection, package_selection_args, target_dir_arg};
+use crate::commands::build_options;
+use anyhow::{Result, anyhow, bail};
+use clap::ArgAction;
+use kelpie_core::{
+    BenchChange, BenchComparison, BenchResults, Builder, KelpieConfig, KelpieContext,
+    LATEST_BENCH_RESULTS, TestFilter, find_config, relative_path,
+};
+use log::{info, warn};
+use std::collections::HashSet;
+use std::env::current_dir;
+
+pub fn bench_cmd() -> clap::Command {
+    package_selection_args(
+        clap::Command::new("bench"),
+        "Package to benchmark, can be repeated",
+    )
+    .about("Compile and run the benchmarks of a project")
+    .arg(target_dir_arg())
+    .arg(
+        opt(
+            "jobs",
+            "Number of packages to compile in parallel, defaults to the CPU count",
+        )
+        .short('j')
+        .value_parser(clap::value_parser!(usize)),
+    )
+    .arg(
+        clap::Arg::new("filters")
+            .help("Only run benchmarks whose name contains one of these")
+            .num_args(0..),
+    )
+    .arg(
+        opt(
+            "exact",
+            "Only run benchmarks whose name is exactly one of the filters",
+        )
+        .action(ArgAction::SetTrue),
+    )
+    .arg(opt("no-run", "Compile the benchmarks but don't run them").action(ArgAction::SetTrue))
+    .arg(
+        opt(
+            "save-baseline",
+            "Save the results as a baseline with this name",
+        )
+        .value_name("NAME"),
+    )
+    .arg(opt("baseline", "Compare the results with a saved baseline").value_name("NAME"))
+    .arg(
+        opt(
+            "noise-threshold",
+            "Changes of at most this many percent are considered noise",
+        )
+        .value_name("PERCENT")
+        .value_parser(clap::value_parser!(f64))
+        .default_value("5"),
+    )
+    .arg(
+        opt(
+            "fail-on-regression",
+            "Exit with an error when a benchmark got slower than the baseline",
+        )
+        .action(ArgAction::SetTrue)
+        .requires("baseline"),
+    )
+}
+
+pub fn bench_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    let targets = package_selection(cli_args).resolve(ctx, project_id)?;
+    if targets.is_empty() {
+        bail!(
+            "Cannot bench in a workspace without specifying a package to bench (use -p <package-name> or --workspace)"
+        )
+    }
+
+    let mut options = build_options(cli_args, config);
+    options.mode = "release";
+    options.bench = true;
+
+    let builder = Builder::new(ctx, project_id, options)?;
+    let results_dir = builder.layout().bench_results();
+
+    // checked before spending time on the benchmarks
+    let baseline = match cli_args.get_one::<String>("baseline") {
+        Some(name) => Some(BenchResults::load(&results_dir, name)?.ok_or_else(|| {
+            anyhow!("baseline `{name}` not found, save one with `kl bench --save-baseline {name}`")
+        })?),
+        None => None,
+    };
+    if let Some(name) = cli_args.get_one::<String>("save-baseline") {
+        BenchResults::path(&results_dir, name)?;
+    }
+
+    let output = builder.build(&targets)?;
+    if cli_args.get_flag("no-run") {
+        return Ok(());
+    }
+
+    let filter = TestFilter {
+        patterns: cli_args
+            .get_many::<String>("filters")
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect(),
+        exact: cli_args.get_flag("exact"),
+    };
+    let threshold = *cli_args.get_one::<f64>("noise-threshold").unwrap();
+
+    let cwd = current_dir()?;
+    let mut results = BenchResults::default();
+    // harnesses that ran, and benchmarks they list that were filtered out
+    let mut harnesses = HashSet::new();
+    let mut not_run = HashSet::new();
+    let mut regressions = 0;
+    for binary in &output.tests {
+        let package = ctx
+            .get_package(binary.target.package)
+            .ok_or_else(|| anyhow!("Failed to get package"))?;
+        let harness = binary.target.binary_name(package);
+        info!(
+            "Running {} ({})",
+            relative_path(&package.root_path, &binary.target.source).display(),
+            relative_path(&cwd, &binary.path).display()
+        );
+
+        for case in binary.list()? {
+            let key = format!("{harness}::{}", case.name);
+            if case.ignored || !filter.matches(&case.name) {
+                not_run.insert(key);
+                continue;
+            }
+
+            let time = binary.bench(&case, &package.root_path)?;
+
+            let comparison = baseline
+                .as_ref()
+                .and_then(|baseline| baseline.results.get(&key))
+                .map(|&previous| BenchComparison::new(previous, time, threshold));
+            match comparison {
+                Some(comparison) => {
+                    let change = match comparison.change {
+                        BenchChange::Improved => "improved",
+                        BenchChange::Regressed => "regressed",
+                        BenchChange::Unchanged => "no change",
+                    };
+                    println!(
+                        "bench {} ... {:.0} ns/iter ({:+.2}%, {})",
+                        case.name, time, comparison.percent, change
+                    );
+                    if comparison.change == BenchChange::Regressed {
+                        regressions += 1;
+                    }
+                }
+                None if baseline.is_some() => {
+                    println!("bench {} ... {:.0} ns/iter (new)", case.name, time)
+                }
+                None => println!("bench {} ... {:.0} ns/iter", case.name, time),
+            }
+
+            results.results.insert(key, time);
+        }
+        harnesses.insert(harness);
+    }
+
+    let keep = |key: &str| {
+        let harness = key.split_once("::").map_or(key, |(harness, _)| harness);
+        !harnesses.contains(harness) || not_run.contains(key)
+    };
+    results.save(&results_dir, LATEST_BENCH_RESULTS, keep)?;
+    if let Some(name) = cli_args.get_one::<String>("save-baseline") {
+        results.save(&results_dir, name, keep)?;
+        info!("Saved baseline `{name}`");
+    }
+
+    if regressions > 0 {
+        if cli_args.get_flag("fail-on-regression") {
+            bail!("{regressions} benchmarks regressed by more than {threshold}%");
+        }
+        warn!("{regressions} benchmarks regressed by more than {threshold}%");
+    }
+
+    Ok(())
+}
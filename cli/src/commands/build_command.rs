@@ -1,121 +1,127 @@
-use crate::cli::{cli, debug_mode, dynamic_lib_mode, package_arg, release_mode, static_lib_mode};
+use crate::cli::{
+    debug_mode, dynamic_lib_mode, opt, package_selection, package_selection_args, release_mode,
+    static_lib_mode, target_dir_arg, target_triple_arg, verbose,
+};
 use anyhow::{Result, bail};
-use kelpie_core::zirael_core::prelude::Mode;
+use clap::ArgAction;
 use kelpie_core::{
-    DependencyGraph, KelpieContext, Package, PackageId, ProjectKind, find_config,
+    BuildOptions, Builder, KelpieConfig, KelpieContext, LibraryType, find_config,
     print_project_tree,
 };
-use log::debug;
 use std::env::current_dir;
-use std::process::Command;
+use std::path::PathBuf;
 
 pub fn build_cmd() -> clap::Command {
-    clap::Command::new("build")
-        .about("Build a project")
-        .arg(release_mode())
-        .arg(debug_mode())
-        .arg(dynamic_lib_mode())
-        .arg(static_lib_mode())
-        .arg(package_arg())
-        .trailing_var_arg(true)
-        .arg(
-            clap::Arg::new("args")
-                .num_args(0..)
-                .allow_negative_numbers(true)
-                .trailing_var_arg(true),
+    package_selection_args(
+        clap::Command::new("build"),
+        "Package to build, can be repeated",
+    )
+    .about("Build a project")
+    .arg(release_mode())
+    .arg(debug_mode())
+    .arg(dynamic_lib_mode())
+    .arg(static_lib_mode())
+    .arg(target_triple_arg())
+    .arg(target_dir_arg())
+    .arg(
+        opt(
+            "jobs",
+            "Number of packages to compile in parallel, defaults to the CPU count",
         )
+        .short('j')
+        .value_parser(clap::value_parser!(usize)),
+    )
+    .arg(
+        opt(
+            "keep-going",
+            "Keep compiling packages that don't depend on a failed one",
+        )
+        .action(ArgAction::SetTrue),
+    )
+    .trailing_var_arg(true)
+    .arg(
+        clap::Arg::new("args")
+            .help("Arguments passed to the compiler for the selected packages, after `--`")
+            .num_args(0..)
+            .allow_negative_numbers(true)
+            .trailing_var_arg(true),
+    )
 }
 
-fn find_package_by_name(
-    ctx: &KelpieContext,
-    name: &str,
-    workspace_members: &[PackageId],
-) -> Option<PackageId> {
-    workspace_members
-        .iter()
-        .find(|&&pkg_id| {
-            ctx.get_package(pkg_id)
-                .map(|pkg| pkg.name == name)
-                .unwrap_or(false)
-        })
-        .copied()
-}
-
-pub fn build_command(cli_args: &clap::ArgMatches) -> Result<()> {
+pub fn build_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
     let ctx = &mut KelpieContext::new();
     let project_id = find_config(current_dir()?, ctx)?;
-    let current_project = ctx
-        .get_project(project_id)
-        .ok_or_else(|| anyhow::anyhow!("Failed to get project"))?;
 
-    print_project_tree(ctx, project_id, 0)?;
+    if verbose(cli_args, config) {
+        print_project_tree(ctx, project_id, 0)?;
+    }
 
-    let target_package = match &current_project.kind {
-        ProjectKind::Package(pkg_id) => *pkg_id,
-        ProjectKind::Workspace(workspace) => {
-            if let Some(package_name) = cli_args.get_one::<String>("package") {
-                find_package_by_name(ctx, package_name, &workspace.members).ok_or_else(|| {
-                    anyhow::anyhow!("Package '{}' not found in workspace", package_name)
-                })?
-            } else {
-                bail!(
-                    "Cannot build in a workspace without specifying a package to build (use -p <package-name>)"
-                )
-            }
-        }
-    };
+    let targets = package_selection(cli_args).resolve(ctx, project_id)?;
+    if targets.is_empty() {
+        bail!(
+            "Cannot build in a workspace without specifying a package to build (use -p <package-name> or --workspace)"
+        )
+    }
 
-    let mut dep_graph = DependencyGraph::new();
-    dep_graph.build_from_project(ctx, project_id)?;
+    let mut options = build_options(cli_args, config);
+    options.args = cli_args
+        .get_many::<String>("args")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
 
-    let compilation_order = dep_graph.get_compilation_order()?;
+    Builder::new(ctx, project_id, options)?.build(&targets)?;
 
-    let mut build_args = vec![];
-    build_args.push("--mode");
-    build_args.push(if cli_args.get_flag("debug") {
-        "debug"
-    } else {
-        "release"
-    });
+    Ok(())
+}
 
-    // todo: temporarily
-    let compiler_path = if cfg!(windows) {
-        "../../zirael/target/debug/zirael.exe"
-    } else {
-        "../../zirael/target/debug/zirael"
+/// The build flags given to a command, falling back to the kelpie configuration.
+pub fn build_options(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> BuildOptions {
+    // not every command accepts all of the build flags
+    let flag = |name: &str| {
+        cli_args
+            .try_get_one::<bool>(name)
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(false)
     };
-    let targeted_pkg = ctx.get_package(target_package).unwrap();
-
-    let mut cmd = Command::new(compiler_path);
-    cmd.args(&build_args).arg("--name").arg(&targeted_pkg.name);
-    cmd.arg(targeted_pkg.full_entrypoint_path());
 
-    cmd.arg("--lib");
-    if cli_args.get_flag("dynamic") {
-        cmd.arg("dynamic");
-    } else if cli_args.get_flag("static") {
-        cmd.arg("static");
+    let lib_types = if flag("dynamic") {
+        Some(vec![LibraryType::Dynamic])
+    } else if flag("static") {
+        Some(vec![LibraryType::Static])
     } else {
-        cmd.arg("dynamic");
-    }
-
-    for package in compilation_order {
-        if package == target_package {
-            continue;
-        }
+        None
+    };
 
-        let pkg = ctx.get_package(package).unwrap();
-        cmd.arg("-d").arg(format!(
-            "{}={}",
-            pkg.name,
-            pkg.full_entrypoint_path().display()
-        ));
+    BuildOptions {
+        mode: if flag("debug") { "debug" } else { "release" },
+        lib_types,
+        target: cli_args
+            .try_get_one::<String>("target")
+            .ok()
+            .flatten()
+            .cloned(),
+        target_dir: cli_args
+            .try_get_one::<PathBuf>("target-dir")
+            .ok()
+            .flatten()
+            .cloned(),
+        configured_target_dir: config.build.target_dir.clone(),
+        jobs: cli_args
+            .try_get_one::<usize>("jobs")
+            .ok()
+            .flatten()
+            .copied()
+            .or(config.build.jobs),
+        compiler: config.toolchain.compiler.clone(),
+        keep_going: flag("keep-going"),
+        args: vec![],
+        check: false,
+        test: false,
+        bench: false,
+        doc_tests: false,
     }
-
-    debug!("{:?}", cmd);
-
-    let status = cmd.status()?;
-    if !status.success() {}
-
-    Ok(())
 }
@@ -0,0 +1,55 @@
+use crate::cli::{
+    debug_mode, opt, package_selection, package_selection_args, release_mode, target_dir_arg,
+    target_triple_arg,
+};
+use crate::commands::build_options;
+use anyhow::{Result, bail};
+use clap::ArgAction;
+use kelpie_core::{Builder, KelpieConfig, KelpieContext, find_config};
+use std::env::current_dir;
+
+pub fn check_cmd() -> clap::Command {
+    package_selection_args(
+        clap::Command::new("check"),
+        "Package to check, can be repeated",
+    )
+    .about("Check a project for errors without generating code")
+    .arg(release_mode())
+    .arg(debug_mode())
+    .arg(target_triple_arg())
+    .arg(target_dir_arg())
+    .arg(
+        opt(
+            "jobs",
+            "Number of packages to check in parallel, defaults to the CPU count",
+        )
+        .short('j')
+        .value_parser(clap::value_parser!(usize)),
+    )
+    .arg(
+        opt(
+            "keep-going",
+            "Keep checking packages that don't depend on a failed one",
+        )
+        .action(ArgAction::SetTrue),
+    )
+}
+
+pub fn check_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    let targets = package_selection(cli_args).resolve(ctx, project_id)?;
+    if targets.is_empty() {
+        bail!(
+            "Cannot check in a workspace without specifying a package to check (use -p <package-name> or --workspace)"
+        )
+    }
+
+    let mut options = build_options(cli_args, config);
+    options.check = true;
+
+    Builder::new(ctx, project_id, options)?.build(&targets)?;
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::cli::{opt, package_selection, package_selection_args, target_dir_arg};
+use anyhow::Result;
+use clap::ArgAction;
+use kelpie_core::zirael_core::prelude::canonicalize_with_strip;
+use kelpie_core::{
+    CleanPlan, KelpieConfig, KelpieContext, find_config, format_size, relative_path,
+    resolve_target_dir,
+};
+use log::info;
+use std::env::current_dir;
+use std::path::PathBuf;
+
+pub fn clean_cmd() -> clap::Command {
+    package_selection_args(
+        clap::Command::new("clean"),
+        "Only remove the artifacts of this package, can be repeated",
+    )
+    .about("Remove the target directory or parts of it")
+    .arg(
+        opt("release", "Only clean the release profile")
+            .short('r')
+            .action(ArgAction::SetTrue)
+            .conflicts_with("profile"),
+    )
+    .arg(opt("profile", "Only clean the given profile"))
+    .arg(target_dir_arg())
+    .arg(
+        opt(
+            "dry-run",
+            "List what would be removed without removing anything",
+        )
+        .short('n')
+        .action(ArgAction::SetTrue),
+    )
+}
+
+pub fn clean_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+    let project = ctx.get_project(project_id).unwrap();
+    let target_dir = resolve_target_dir(
+        project,
+        cli_args
+            .get_one::<PathBuf>("target-dir")
+            .map(PathBuf::as_path),
+        config.build.target_dir.as_deref(),
+    )?;
+
+    let profile = if cli_args.get_flag("release") {
+        Some("release")
+    } else {
+        cli_args.get_one::<String>("profile").map(String::as_str)
+    };
+
+    // without -p or --workspace everything goes, not just the current package
+    let selection = package_selection(cli_args);
+    let packages = if selection.packages.is_empty() && !selection.workspace {
+        vec![]
+    } else {
+        selection.resolve(ctx, project_id)?
+    };
+    let names: Vec<_> = packages
+        .iter()
+        .map(|&id| ctx.get_package(id).unwrap().name.as_str())
+        .collect();
+
+    let plan = CleanPlan::new(&target_dir, profile, &names)?;
+    let (files, size) = plan.stats()?;
+
+    if cli_args.get_flag("dry-run") {
+        let cwd = canonicalize_with_strip(current_dir()?)?;
+        for path in &plan.paths {
+            info!("Would remove {}", relative_path(&cwd, path).display());
+        }
+        info!("Would remove {} files, {} total", files, format_size(size));
+        return Ok(());
+    }
+
+    plan.execute()?;
+    info!("Removed {} files, {} total", files, format_size(size));
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use crate::cli::{opt, package_selection, package_selection_args, target_dir_arg};
+use anyhow::{Result, anyhow};
+use clap::ArgAction;
+use kelpie_core::{
+    Compiler, DocOptions, Documenter, KelpieConfig, KelpieContext, ProjectKind, find_config,
+    relative_path,
+};
+use log::info;
+use std::env::current_dir;
+use std::path::PathBuf;
+
+pub fn doc_cmd() -> clap::Command {
+    package_selection_args(
+        clap::Command::new("doc"),
+        "Package to document, can be repeated",
+    )
+    .about("Generate the documentation of a project and its dependencies")
+    .arg(target_dir_arg())
+    .arg(
+        opt(
+            "jobs",
+            "Number of packages to document in parallel, defaults to the CPU count",
+        )
+        .short('j')
+        .value_parser(clap::value_parser!(usize)),
+    )
+    .arg(
+        opt("no-deps", "Don't document the dependencies of the packages")
+            .action(ArgAction::SetTrue),
+    )
+}
+
+pub fn doc_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    // unlike building, a workspace documents all of its members by default
+    let mut targets = package_selection(cli_args).resolve(ctx, project_id)?;
+    if targets.is_empty() {
+        let project = ctx
+            .get_project(project_id)
+            .ok_or_else(|| anyhow!("Failed to get project"))?;
+        if let ProjectKind::Workspace(workspace) = &project.kind {
+            targets = workspace.members.clone();
+        }
+    }
+
+    let options = DocOptions {
+        no_deps: cli_args.get_flag("no-deps"),
+        target_dir: cli_args.get_one::<PathBuf>("target-dir").cloned(),
+        configured_target_dir: config.build.target_dir.clone(),
+        jobs: cli_args
+            .get_one::<usize>("jobs")
+            .copied()
+            .or(config.build.jobs),
+    };
+    let mut documenter = Documenter::new(ctx, project_id, options);
+    if let Some(compiler) = config.toolchain.compiler.clone() {
+        documenter = documenter.compiler(Compiler::new(compiler));
+    }
+    let index = documenter.document(&targets)?;
+    info!(
+        "Generated {}",
+        relative_path(&current_dir()?, &index).display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use anyhow::{Result, anyhow, bail};
+use kelpie_core::{KelpieContext, ManifestEditor, ProjectKind, find_config};
+use std::env::current_dir;
+use std::path::{Path, PathBuf};
+
+/// Manifests touched by `kl add` and `kl remove`.
+pub struct EditTarget {
+    pub root_manifest: PathBuf,
+    /// Manifest of the selected package, `None` when only the workspace manifest is edited.
+    pub package_manifest: Option<PathBuf>,
+    pub is_workspace: bool,
+}
+
+pub fn find_edit_target(package: Option<&String>) -> Result<EditTarget> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+    let project = ctx
+        .get_project(project_id)
+        .ok_or_else(|| anyhow!("Failed to get project"))?;
+
+    match &project.kind {
+        ProjectKind::Package(pkg_id) => {
+            let pkg = ctx.get_package(*pkg_id).unwrap();
+            if let Some(package) = package.filter(|package| **package != pkg.name) {
+                bail!("Package '{}' not found", package);
+            }
+
+            Ok(EditTarget {
+                root_manifest: project.manifest_path.clone(),
+                package_manifest: Some(project.manifest_path.clone()),
+                is_workspace: false,
+            })
+        }
+        ProjectKind::Workspace(workspace) => {
+            let package_manifest = if let Some(package) = package {
+                let member = workspace
+                    .members
+                    .iter()
+                    .find(|&&id| ctx.get_package(id).is_some_and(|pkg| pkg.name == *package))
+                    .ok_or_else(|| anyhow!("Package '{}' not found in workspace", package))?;
+                let member_project = ctx.find_project_by_package_id(*member).unwrap();
+                Some(
+                    ctx.get_project(member_project)
+                        .unwrap()
+                        .manifest_path
+                        .clone(),
+                )
+            } else {
+                None
+            };
+
+            Ok(EditTarget {
+                root_manifest: project.manifest_path.clone(),
+                package_manifest,
+                is_workspace: true,
+            })
+        }
+    }
+}
+
+/// Loads the project with the edited manifests and only writes them if it still resolves.
+pub fn verify_and_save(root_manifest: &Path, editors: &[&ManifestEditor]) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    for editor in editors {
+        ctx.override_manifest(editor.path(), editor.contents())?;
+    }
+
+    ctx.load_from_manifest(root_manifest, false)?;
+
+    for editor in editors {
+        editor.save()?;
+    }
+    Ok(())
+}
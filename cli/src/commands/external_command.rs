@@ -0,0 +1,49 @@
+use anyhow::{Result, bail};
+use kelpie_core::{Aliases, find_plugin, list_plugins, run_plugin};
+use std::ffi::OsString;
+use std::process::exit;
+
+/// Runs the `kl-<name>` plugin for a command clap doesn't know, forwarding the arguments and
+/// its exit code.
+pub fn external_command(name: &str, cli_args: &clap::ArgMatches) -> Result<()> {
+    let Some(plugin) = find_plugin(name) else {
+        bail!("no such command `{name}`, see `kl --list` for the available commands");
+    };
+
+    let args: Vec<OsString> = cli_args
+        .get_many::<OsString>("")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    let status = run_plugin(&plugin, &args)?;
+    if !status.success() {
+        exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// Prints the built-in commands with their description, then the aliases and the plugins with
+/// their path. Plugins named like a built-in command or alias can't be run, so they aren't
+/// listed.
+pub fn list_commands(cli: &clap::Command, aliases: &Aliases) {
+    println!("Installed commands:");
+
+    let mut builtins: Vec<_> = cli.get_subcommands().collect();
+    builtins.sort_by_key(|cmd| cmd.get_name());
+    for cmd in &builtins {
+        let about = cmd.get_about().map(ToString::to_string).unwrap_or_default();
+        println!("    {:<16}{}", cmd.get_name(), about);
+    }
+
+    for (name, expansion) in aliases.iter() {
+        println!("    {:<16}alias: {}", name, expansion.join(" "));
+    }
+
+    for (name, path) in list_plugins() {
+        if builtins.iter().all(|cmd| cmd.get_name() != name) && aliases.get(&name).is_none() {
+            println!("    {:<16}{}", name, path.display());
+        }
+    }
+}
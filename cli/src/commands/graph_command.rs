@@ -0,0 +1,64 @@
+use crate::cli::opt;
+use anyhow::{Result, bail};
+use clap::ArgAction;
+use kelpie_core::{
+    DependencyKind, GraphFormat, KelpieContext, PackageGraph, PackageSpec, PackageTree, find_config,
+};
+use std::env::current_dir;
+
+pub fn graph_cmd() -> clap::Command {
+    clap::Command::new("graph")
+        .about("Output the dependency graph of a project as a diagram")
+        .arg(
+            opt("format", "Diagram format")
+                .value_parser(["dot", "mermaid"])
+                .default_value("dot"),
+        )
+        .arg(
+            opt(
+                "focus",
+                "Only show this package, its dependencies and its dependents, can be repeated",
+            )
+            .value_name("SPEC")
+            .action(ArgAction::Append),
+        )
+        .arg(
+            opt(
+                "highlight-duplicates",
+                "Highlight the packages present in several versions",
+            )
+            .action(ArgAction::SetTrue),
+        )
+}
+
+pub fn graph_command(cli_args: &clap::ArgMatches) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    let tree = PackageTree::new(ctx, project_id, &DependencyKind::ALL)?;
+    let mut graph = PackageGraph::new(ctx, &tree);
+
+    if let Some(specs) = cli_args.get_many::<String>("focus") {
+        let mut focus = vec![];
+        for spec in specs {
+            let parsed = PackageSpec::parse(spec)?;
+            let found = tree.find(|package| parsed.matches(package));
+            if found.is_empty() {
+                bail!("Package '{}' not found in the dependency tree", spec);
+            }
+            focus.extend(found);
+        }
+        graph.focus(&focus);
+    }
+    if cli_args.get_flag("highlight-duplicates") {
+        graph.highlight(tree.duplicates().into_values().flatten());
+    }
+
+    let format = cli_args
+        .get_one::<String>("format")
+        .unwrap()
+        .parse::<GraphFormat>()?;
+    print!("{}", graph.render(format));
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::cli::opt;
+use anyhow::Result;
+use clap::ArgAction;
+use kelpie_core::{KelpieConfig, KelpieContext, Metadata, find_config};
+use std::env::current_dir;
+
+pub fn metadata_cmd() -> clap::Command {
+    clap::Command::new("metadata")
+        .about("Output the packages of a project and their dependencies as JSON")
+        .arg(
+            opt("format-version", "Version of the output format")
+                .value_parser(clap::value_parser!(u32).range(1..=1))
+                .default_value("1"),
+        )
+        .arg(
+            opt(
+                "no-deps",
+                "Only describe the workspace members, without resolving dependencies",
+            )
+            .action(ArgAction::SetTrue),
+        )
+}
+
+pub fn metadata_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    let metadata = Metadata::new(
+        ctx,
+        project_id,
+        *cli_args.get_one::<u32>("format-version").unwrap(),
+        cli_args.get_flag("no-deps"),
+        None,
+        config.build.target_dir.as_deref(),
+    )?;
+    print!("{}", metadata.to_json()?);
+
+    Ok(())
+}
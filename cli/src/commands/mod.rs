@@ -1,2 +1,31 @@
+mod add_command;
+mod bench_command;
 mod build_command;
+mod check_command;
+mod clean_command;
+mod doc_command;
+mod edit;
+mod external_command;
+mod graph_command;
+mod metadata_command;
+mod new_command;
+mod remove_command;
+mod run_command;
+mod test_command;
+mod tree_command;
+mod why_command;
+pub use add_command::*;
+pub use bench_command::*;
 pub use build_command::*;
+pub use check_command::*;
+pub use clean_command::*;
+pub use doc_command::*;
+pub use external_command::*;
+pub use graph_command::*;
+pub use metadata_command::*;
+pub use new_command::*;
+pub use remove_command::*;
+pub use run_command::*;
+pub use test_command::*;
+pub use tree_command::*;
+pub use why_command::*;
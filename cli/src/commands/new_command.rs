@@ -0,0 +1,208 @@
+use crate::cli::{opt, positional};
+use anyhow::{Result, anyhow, bail};
+use clap::ArgAction;
+use kelpie_core::{
+    CreatedPackage, NewPackage, TEMPLATES_DIR, Template, builtin_template_values, kelpie_home,
+    substitute,
+};
+use log::info;
+use std::collections::HashMap;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use zirael_utils::prelude::PackageType;
+
+pub const TEMPLATE_HEADING: &str = "Template options";
+
+fn package_args(cmd: clap::Command) -> clap::Command {
+    cmd.arg(
+        opt("lib", "Use a library template (default)")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("bin"),
+    )
+    .arg(
+        opt("bin", "Use a binary (application) template")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("lib"),
+    )
+    .arg(opt(
+        "name",
+        "Set the package name, defaults to the directory name",
+    ))
+    .arg(
+        opt(
+            "template",
+            "Template to create the package from: a name, a path or a git URL",
+        )
+        .help_heading(TEMPLATE_HEADING),
+    )
+    .arg(
+        opt(
+            "define",
+            "Set a template placeholder without prompting (`key=value`)",
+        )
+        .short('D')
+        .action(ArgAction::Append)
+        .requires("template")
+        .help_heading(TEMPLATE_HEADING),
+    )
+    .arg(
+        opt(
+            "list-templates",
+            "List the templates installed in the kelpie home and exit",
+        )
+        .action(ArgAction::SetTrue)
+        .conflicts_with_all(["lib", "bin", "name", "template", "define"])
+        .help_heading(TEMPLATE_HEADING),
+    )
+}
+
+pub fn new_cmd() -> clap::Command {
+    package_args(
+        clap::Command::new("new").about("Create a new package").arg(
+            positional("path", "Directory to create the package in")
+                .required_unless_present("list-templates"),
+        ),
+    )
+}
+
+pub fn init_cmd() -> clap::Command {
+    package_args(
+        clap::Command::new("init")
+            .about("Create a new package in an existing directory")
+            .arg(positional("path", "Directory to create the package in").default_value(".")),
+    )
+}
+
+fn new_package(cli_args: &clap::ArgMatches) -> Result<NewPackage> {
+    let ty = if cli_args.get_flag("bin") {
+        PackageType::Binary
+    } else {
+        PackageType::Library
+    };
+    let path = PathBuf::from(cli_args.get_one::<String>("path").unwrap());
+    let package = NewPackage::new(path, ty).name(cli_args.get_one::<String>("name").cloned());
+
+    let Some(spec) = cli_args.get_one::<String>("template") else {
+        return Ok(package);
+    };
+
+    let template = Template::resolve(spec)?;
+    let values = template_values(&template, &package.package_name()?, cli_args)?;
+    Ok(package.template(Some(template)).values(values))
+}
+
+/// Fills the template placeholders from `--define`, prompting for the rest when interactive.
+fn template_values(
+    template: &Template,
+    name: &str,
+    cli_args: &clap::ArgMatches,
+) -> Result<HashMap<String, String>> {
+    let mut values = builtin_template_values(name);
+    for define in cli_args.get_many::<String>("define").into_iter().flatten() {
+        let (key, value) = define.split_once('=').ok_or_else(|| {
+            anyhow!(
+                "invalid placeholder definition `{}`, expected `key=value`",
+                define
+            )
+        })?;
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+
+    let interactive = io::stdin().is_terminal();
+    for placeholder in &template.placeholders {
+        if values.contains_key(&placeholder.name) {
+            continue;
+        }
+
+        let default = placeholder
+            .default
+            .as_ref()
+            .map(|default| substitute(default, &values));
+
+        let value = if interactive {
+            prompt(&placeholder.prompt, default.as_deref())?
+        } else {
+            default
+        };
+
+        let Some(value) = value else {
+            bail!(
+                "no value for template placeholder `{}` (use -D {}=<value>)",
+                placeholder.name,
+                placeholder.name
+            );
+        };
+        values.insert(placeholder.name.clone(), value);
+    }
+
+    Ok(values)
+}
+
+fn prompt(message: &str, default: Option<&str>) -> Result<Option<String>> {
+    let mut stderr = io::stderr().lock();
+    match default {
+        Some(default) => write!(stderr, "{message} [{default}]: ")?,
+        None => write!(stderr, "{message}: ")?,
+    }
+    stderr.flush()?;
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    let line = line.trim();
+
+    if line.is_empty() {
+        Ok(default.map(str::to_string))
+    } else {
+        Ok(Some(line.to_string()))
+    }
+}
+
+fn report(created: &CreatedPackage) {
+    let kind = match created.ty {
+        PackageType::Library => "library",
+        PackageType::Binary => "binary (application)",
+    };
+    info!("Created {} `{}` package", kind, created.name);
+
+    if created.workspace_manifest.is_some() {
+        info!("Added `{}` to the workspace members", created.name);
+    }
+}
+
+/// Prints the templates `--template` can name, with their description.
+fn list_templates() -> Result<()> {
+    let templates = Template::available()?;
+    if templates.is_empty() {
+        info!(
+            "no templates are installed in {}",
+            kelpie_home()?.join(TEMPLATES_DIR).display()
+        );
+        return Ok(());
+    }
+
+    println!("Installed templates:");
+    for (name, description) in templates {
+        println!("    {:<16}{}", name, description.unwrap_or_default());
+    }
+    Ok(())
+}
+
+pub fn new_command(cli_args: &clap::ArgMatches) -> Result<()> {
+    if cli_args.get_flag("list-templates") {
+        return list_templates();
+    }
+
+    let created = new_package(cli_args)?.create()?;
+    report(&created);
+    Ok(())
+}
+
+pub fn init_command(cli_args: &clap::ArgMatches) -> Result<()> {
+    if cli_args.get_flag("list-templates") {
+        return list_templates();
+    }
+
+    let created = new_package(cli_args)?.init()?;
+    report(&created);
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use crate::cli::{package_arg, positional};
+use crate::commands::add_command::{dependency_kind, dependency_kind_args};
+use crate::commands::edit::{find_edit_target, verify_and_save};
+use anyhow::{Result, bail};
+use kelpie_core::{DependencyKind, ManifestEditor};
+use log::info;
+
+pub fn remove_cmd() -> clap::Command {
+    let cmd = clap::Command::new("remove")
+        .about("Remove a dependency from a manifest")
+        .visible_alias("rm")
+        .arg(positional("dependency", "Dependency to remove").required(true))
+        .arg(package_arg().help("Package to remove the dependency from"));
+
+    dependency_kind_args(cmd)
+}
+
+pub fn remove_command(cli_args: &clap::ArgMatches) -> Result<()> {
+    let kind = dependency_kind(cli_args);
+    let name = cli_args.get_one::<String>("dependency").unwrap();
+    let target = find_edit_target(cli_args.get_one::<String>("package"))?;
+
+    // without a package, a workspace only has its shared dependencies to remove from
+    let (editor, table) = match &target.package_manifest {
+        Some(package_manifest) => {
+            let mut editor = ManifestEditor::open(package_manifest)?;
+            editor.remove_dependency(kind, name)?;
+            (editor, kind.table_name())
+        }
+        None => {
+            if kind != DependencyKind::Normal {
+                bail!("[workspace.dependencies] can't contain dev or build dependencies");
+            }
+            let mut editor = ManifestEditor::open(&target.root_manifest)?;
+            editor.remove_workspace_dependency(name)?;
+            (editor, "workspace.dependencies")
+        }
+    };
+
+    verify_and_save(&target.root_manifest, &[&editor])?;
+    info!("Removed {} from [{}]", name, table);
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::cli::{debug_mode, opt, package_arg, release_mode, target_dir_arg, target_triple_arg};
+use crate::commands::build_options;
+use anyhow::{Result, anyhow, bail};
+use kelpie_core::{
+    Builder, KelpieConfig, KelpieContext, PackageId, PackageSelection, ProjectId, ProjectKind,
+    find_config,
+};
+use log::debug;
+use std::env::current_dir;
+use std::process::{Command, exit};
+use zirael_utils::prelude::PackageType;
+
+pub fn run_cmd() -> clap::Command {
+    clap::Command::new("run")
+        .about("Build and run a binary package")
+        .arg(release_mode())
+        .arg(debug_mode())
+        .arg(package_arg().help("Package to run"))
+        .arg(target_triple_arg())
+        .arg(target_dir_arg())
+        .arg(opt("bin", "Name of the binary package to run").conflicts_with("package"))
+        .trailing_var_arg(true)
+        .arg(
+            clap::Arg::new("args")
+                .help("Arguments passed to the program")
+                .num_args(0..)
+                .allow_hyphen_values(true)
+                .trailing_var_arg(true),
+        )
+}
+
+fn select_binary(
+    ctx: &KelpieContext,
+    project_id: ProjectId,
+    cli_args: &clap::ArgMatches,
+) -> Result<PackageId> {
+    let requested = cli_args
+        .get_one::<String>("package")
+        .or_else(|| cli_args.get_one::<String>("bin"));
+
+    let selection = PackageSelection {
+        packages: requested.into_iter().cloned().collect(),
+        ..Default::default()
+    };
+    let mut candidates = selection.resolve(ctx, project_id)?;
+
+    let package = |id: PackageId| {
+        ctx.get_package(id)
+            .ok_or_else(|| anyhow!("Failed to get package"))
+    };
+
+    // nothing was requested in a workspace, so look for its only binary
+    if candidates.is_empty() {
+        let project = ctx
+            .get_project(project_id)
+            .ok_or_else(|| anyhow!("Failed to get project"))?;
+        if let ProjectKind::Workspace(workspace) = &project.kind {
+            for &id in &workspace.members {
+                if package(id)?.ty == PackageType::Binary {
+                    candidates.push(id);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            bail!("the workspace doesn't contain any binary packages");
+        }
+    }
+
+    let mut names = vec![];
+    for &id in &candidates {
+        let pkg = package(id)?;
+        if pkg.ty != PackageType::Binary {
+            bail!("cannot run package {} because it is a library", pkg.name);
+        }
+        names.push(pkg.name.as_str());
+    }
+
+    match candidates.as_slice() {
+        [package] => Ok(*package),
+        _ => bail!(
+            "could not determine which binary to run, use -p or --bin to pick one of: {}",
+            names.join(", ")
+        ),
+    }
+}
+
+pub fn run_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+    let package = select_binary(ctx, project_id, cli_args)?;
+    let output =
+        Builder::new(ctx, project_id, build_options(cli_args, config))?.build(&[package])?;
+
+    let executable = &output.artifacts(package)[0].path;
+    if !executable.exists() {
+        bail!("executable {} wasn't produced", executable.display());
+    }
+
+    let mut cmd = Command::new(executable);
+    cmd.args(cli_args.get_many::<String>("args").into_iter().flatten());
+    debug!("{:?}", cmd);
+
+    let status = cmd.status()?;
+    if !status.success() {
+        exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,329 @@
+use crate::cli::{
+    debug_mode, opt, package_selection, package_selection_args, release_mode, target_dir_arg,
+};
+use crate::commands::build_options;
+use anyhow::{Result, anyhow, bail};
+use clap::ArgAction;
+use kelpie_core::{
+    BuildOutput, Builder, DocTest, KelpieConfig, KelpieContext, Package, PackageId, ReportFormat,
+    TestBinary, TestCase, TestFilter, TestOutcome, TestReport, TestResult, TestShard, TestSuite,
+    TestSummary, TestTargetKind, extract_doc_tests, find_config, relative_path,
+};
+use log::{info, warn};
+use std::env::current_dir;
+use std::path::PathBuf;
+use zirael_utils::prelude::PackageType;
+
+pub fn test_cmd() -> clap::Command {
+    package_selection_args(
+        clap::Command::new("test"),
+        "Package to test, can be repeated",
+    )
+    .about("Compile and run the tests of a project")
+    .arg(release_mode())
+    .arg(debug_mode())
+    .arg(target_dir_arg())
+    .arg(
+        opt(
+            "jobs",
+            "Number of packages to compile in parallel, defaults to the CPU count",
+        )
+        .short('j')
+        .value_parser(clap::value_parser!(usize)),
+    )
+    .arg(
+        clap::Arg::new("filters")
+            .help("Only run tests whose name contains one of these")
+            .num_args(0..),
+    )
+    .arg(
+        opt(
+            "exact",
+            "Only run tests whose name is exactly one of the filters",
+        )
+        .action(ArgAction::SetTrue),
+    )
+    .arg(opt("no-run", "Compile the tests but don't run them").action(ArgAction::SetTrue))
+    .arg(
+        opt(
+            "doc",
+            "Only run the examples in the documentation of the packages",
+        )
+        .action(ArgAction::SetTrue),
+    )
+    .arg(
+        opt(
+            "no-fail-fast",
+            "Run every test binary instead of stopping after the first one that fails",
+        )
+        .action(ArgAction::SetTrue),
+    )
+    .arg(
+        opt("format", "Write the results as a report in this format")
+            .value_parser(["junit", "json"]),
+    )
+    .arg(
+        opt("output", "File the report is written to, instead of stdout")
+            .short('o')
+            .value_parser(clap::value_parser!(PathBuf))
+            .requires("format"),
+    )
+    .arg(
+        opt(
+            "shard",
+            "Only run the <index>th of <count> slices of the tests, split by name",
+        )
+        .value_name("INDEX/COUNT")
+        .value_parser(|s: &str| s.parse::<TestShard>().map_err(|e| e.to_string())),
+    )
+}
+
+/// Tests that run together and are reported as one suite.
+struct Suite<'a> {
+    package: PackageId,
+    name: String,
+    kind: TestTargetKind,
+    /// Shown when the suite starts running.
+    description: String,
+    runner: Runner<'a>,
+}
+
+enum Runner<'a> {
+    Harness(&'a TestBinary),
+    Doc {
+        tests: Vec<DocTest>,
+        dependencies: Vec<(String, PathBuf)>,
+    },
+}
+
+impl Suite<'_> {
+    fn cases(&self) -> Result<Vec<TestCase>> {
+        match &self.runner {
+            Runner::Harness(binary) => binary.list(),
+            Runner::Doc { tests, .. } => Ok(tests
+                .iter()
+                .map(|test| TestCase {
+                    name: test.name.clone(),
+                    ignored: test.ignore,
+                })
+                .collect()),
+        }
+    }
+
+    fn run(&self, builder: &Builder, case: &TestCase, package: &Package) -> Result<TestResult> {
+        match &self.runner {
+            Runner::Harness(binary) => binary.run(case, &package.root_path),
+            Runner::Doc {
+                tests,
+                dependencies,
+            } => {
+                let index = tests
+                    .iter()
+                    .position(|test| test.name == case.name)
+                    .ok_or_else(|| anyhow!("unknown doc test {}", case.name))?;
+                builder.run_doc_test(self.package, &tests[index], index, dependencies)
+            }
+        }
+    }
+}
+
+pub fn test_command(cli_args: &clap::ArgMatches, config: &KelpieConfig) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    let targets = package_selection(cli_args).resolve(ctx, project_id)?;
+    if targets.is_empty() {
+        bail!(
+            "Cannot test in a workspace without specifying a package to test (use -p <package-name> or --workspace)"
+        )
+    }
+
+    let doc = cli_args.get_flag("doc");
+    let no_fail_fast = cli_args.get_flag("no-fail-fast");
+    let mut options = build_options(cli_args, config);
+    options.test = !doc;
+    options.doc_tests = doc;
+    options.keep_going = no_fail_fast;
+
+    let builder = Builder::new(ctx, project_id, options)?;
+    let output = builder.build(&targets)?;
+    if cli_args.get_flag("no-run") {
+        return Ok(());
+    }
+
+    let cwd = current_dir()?;
+    let suites = if doc {
+        doc_test_suites(ctx, &builder, &targets, &output)?
+    } else {
+        output
+            .tests
+            .iter()
+            .map(|binary| {
+                let package = ctx
+                    .get_package(binary.target.package)
+                    .ok_or_else(|| anyhow!("Failed to get package"))?;
+                let source = match binary.target.kind {
+                    TestTargetKind::Unit => "unit tests".to_string(),
+                    _ => relative_path(&package.root_path, &binary.target.source)
+                        .display()
+                        .to_string(),
+                };
+
+                Ok(Suite {
+                    package: binary.target.package,
+                    name: binary.target.binary_name(package),
+                    kind: binary.target.kind,
+                    description: format!(
+                        "{} ({})",
+                        source,
+                        relative_path(&cwd, &binary.path).display()
+                    ),
+                    runner: Runner::Harness(binary),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let filter = TestFilter {
+        patterns: cli_args
+            .get_many::<String>("filters")
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect(),
+        exact: cli_args.get_flag("exact"),
+    };
+
+    let format = cli_args
+        .get_one::<String>("format")
+        .map(|format| format.parse::<ReportFormat>())
+        .transpose()?;
+    let report_path = cli_args.get_one::<PathBuf>("output");
+    // the report takes over stdout when it isn't written to a file
+    let quiet = format.is_some() && report_path.is_none();
+
+    let mut cases = vec![];
+    for (index, suite) in suites.iter().enumerate() {
+        for case in suite.cases()? {
+            if filter.matches(&case.name) {
+                cases.push((index, case));
+            }
+        }
+    }
+
+    // names are qualified by the suite, the same test can be in several of them
+    if let Some(shard) = cli_args.get_one::<TestShard>("shard") {
+        cases = shard.select(cases, |(index, case): &(usize, TestCase)| {
+            format!("{}::{}", suites[*index].name, case.name)
+        });
+    }
+
+    let mut report = TestReport::default();
+    let mut failed_packages = vec![];
+    for &target in &targets {
+        let package = ctx
+            .get_package(target)
+            .ok_or_else(|| anyhow!("Failed to get package"))?;
+
+        let mut summary = TestSummary::default();
+        for (index, suite) in suites
+            .iter()
+            .enumerate()
+            .filter(|(_, suite)| suite.package == target)
+        {
+            info!("Running {}", suite.description);
+
+            let mut results = vec![];
+            for (_, case) in cases.iter().filter(|(suite, _)| *suite == index) {
+                let result = suite.run(&builder, case, package)?;
+                if !quiet {
+                    println!("test {} ... {}", result.name, result.outcome.as_str());
+                }
+                summary.add(result.outcome);
+                results.push(result);
+            }
+
+            let failures: Vec<_> = results
+                .iter()
+                .filter(|result| result.outcome == TestOutcome::Failed)
+                .collect();
+            for failure in failures.iter().filter(|_| !quiet) {
+                println!(
+                    "\n---- {} output ----\n{}",
+                    failure.name,
+                    failure.output.trim_end()
+                );
+            }
+
+            let failed = !failures.is_empty();
+            report.suites.push(TestSuite {
+                package: package.name.clone(),
+                name: suite.name.clone(),
+                kind: suite.kind,
+                results,
+            });
+            if failed && !no_fail_fast {
+                break;
+            }
+        }
+
+        info!(
+            "{}: {} passed, {} failed, {} ignored",
+            package.name, summary.passed, summary.failed, summary.ignored
+        );
+
+        if !summary.is_success() {
+            failed_packages.push(package.name.clone());
+            if !no_fail_fast {
+                break;
+            }
+        }
+    }
+
+    if let Some(format) = format {
+        let rendered = report.render(format)?;
+        match report_path {
+            Some(path) => fs_err::write(path, rendered)?,
+            None => print!("{rendered}"),
+        }
+    }
+
+    match failed_packages.as_slice() {
+        [] => Ok(()),
+        [package] => bail!("tests of package {} failed", package),
+        packages => {
+            warn!("tests failed in: {}", packages.join(", "));
+            bail!("tests of {} packages failed", packages.len())
+        }
+    }
+}
+
+/// A suite with the doc tests of each library in `targets`, binaries can't be linked against.
+fn doc_test_suites<'a>(
+    ctx: &KelpieContext,
+    builder: &Builder,
+    targets: &[PackageId],
+    output: &BuildOutput,
+) -> Result<Vec<Suite<'a>>> {
+    let mut suites = vec![];
+    for &target in targets {
+        let package = ctx
+            .get_package(target)
+            .ok_or_else(|| anyhow!("Failed to get package"))?;
+        if package.ty != PackageType::Library {
+            continue;
+        }
+
+        suites.push(Suite {
+            package: target,
+            name: format!("{}-doctests", package.name),
+            kind: TestTargetKind::Doc,
+            description: format!("doc tests of {}", package.name),
+            runner: Runner::Doc {
+                tests: extract_doc_tests(package)?,
+                dependencies: builder.doc_test_dependencies(target, output)?,
+            },
+        });
+    }
+    Ok(suites)
+}
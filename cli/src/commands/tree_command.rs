@@ -0,0 +1,105 @@
+use crate::cli::{opt, package_selection, package_selection_args};
+use anyhow::{Result, bail};
+use clap::ArgAction;
+use kelpie_core::{
+    DependencyKind, KelpieContext, PackageSpec, PackageTree, TreeOptions, TreePrefix, find_config,
+    parse_edge_kind,
+};
+use std::env::current_dir;
+
+pub fn tree_cmd() -> clap::Command {
+    package_selection_args(
+        clap::Command::new("tree"),
+        "Package to show the tree of, can be repeated",
+    )
+    .about("Display the dependency tree of a project")
+    .arg(
+        opt("depth", "Maximum depth of the tree, 0 only shows the roots")
+            .value_parser(clap::value_parser!(usize)),
+    )
+    .arg(
+        opt(
+            "invert",
+            "Show the packages that depend on this package instead",
+        )
+        .short('i')
+        .value_name("SPEC")
+        .action(ArgAction::Append)
+        .conflicts_with_all(["package", "workspace"]),
+    )
+    .arg(
+        opt(
+            "duplicates",
+            "Show the packages present in several versions and who depends on them",
+        )
+        .short('d')
+        .action(ArgAction::SetTrue)
+        .conflicts_with("invert"),
+    )
+    .arg(
+        opt(
+            "edges",
+            "Kinds of dependencies to show, defaults to all of them",
+        )
+        .short('e')
+        .value_name("KINDS")
+        .value_delimiter(',')
+        .action(ArgAction::Append)
+        .value_parser(["normal", "dev", "build"]),
+    )
+    .arg(
+        opt("prefix", "How each line of the tree starts")
+            .value_parser(["none", "indent", "depth"])
+            .default_value("indent"),
+    )
+}
+
+pub fn tree_command(cli_args: &clap::ArgMatches) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    let kinds = match cli_args.get_many::<String>("edges") {
+        Some(kinds) => kinds
+            .map(|kind| parse_edge_kind(kind))
+            .collect::<Result<Vec<_>>>()?,
+        None => DependencyKind::ALL.to_vec(),
+    };
+    let options = TreeOptions {
+        depth: cli_args.get_one::<usize>("depth").copied(),
+        prefix: cli_args
+            .get_one::<String>("prefix")
+            .unwrap()
+            .parse::<TreePrefix>()?,
+    };
+    let tree = PackageTree::new(ctx, project_id, &kinds)?;
+
+    let output = if cli_args.get_flag("duplicates") {
+        let roots: Vec<_> = tree.duplicates().into_values().flatten().collect();
+        tree.invert().render(&roots, &options)
+    } else if let Some(specs) = cli_args.get_many::<String>("invert") {
+        let mut roots = vec![];
+        for spec in specs {
+            let parsed = PackageSpec::parse(spec)?;
+            let found = tree.find(|package| parsed.matches(package));
+            if found.is_empty() {
+                bail!("Package '{}' not found in the dependency tree", spec);
+            }
+            for package_id in found {
+                if !roots.contains(&package_id) {
+                    roots.push(package_id);
+                }
+            }
+        }
+        tree.invert().render(&roots, &options)
+    } else {
+        // like `kl doc`, a workspace shows all of its members by default
+        let mut roots = package_selection(cli_args).resolve(ctx, project_id)?;
+        if roots.is_empty() {
+            roots = tree.members().to_vec();
+        }
+        tree.render(&roots, &options)
+    };
+    print!("{output}");
+
+    Ok(())
+}
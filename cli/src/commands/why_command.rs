@@ -0,0 +1,59 @@
+use crate::cli::positional;
+use anyhow::{Result, bail};
+use kelpie_core::{
+    DependencyKind, KelpieContext, PackageSpec, PackageTree, find_config, shortest_paths,
+};
+use log::info;
+use std::env::current_dir;
+
+pub fn why_cmd() -> clap::Command {
+    clap::Command::new("why")
+        .about("Explain why a package is part of the dependency graph")
+        .arg(
+            positional("package", "Package to explain, as `name` or `name@version`")
+                .value_name("SPEC")
+                .required(true),
+        )
+}
+
+pub fn why_command(cli_args: &clap::ArgMatches) -> Result<()> {
+    let ctx = &mut KelpieContext::new();
+    let project_id = find_config(current_dir()?, ctx)?;
+
+    let spec = cli_args.get_one::<String>("package").unwrap();
+    let parsed = PackageSpec::parse(spec)?;
+    let tree = PackageTree::new(ctx, project_id, &DependencyKind::ALL)?;
+    let found = tree.find(|package| parsed.matches(package));
+    if found.is_empty() {
+        bail!("Package '{}' not found in the dependency tree", spec);
+    }
+
+    for (index, &package_id) in found.iter().enumerate() {
+        let Some(package) = ctx.get_package(package_id) else {
+            continue;
+        };
+        if tree.members().contains(&package_id) {
+            info!(
+                "{} v{} is a workspace member",
+                package.name, package.version
+            );
+        }
+
+        let mut paths: Vec<String> = shortest_paths(&tree, package_id)
+            .iter()
+            .map(|path| path.display(ctx))
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        if index > 0 {
+            println!();
+        }
+        println!("{} v{}", package.name, package.version);
+        for path in paths {
+            println!("    {path}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::KelpieConfig;
+use anyhow::{Result, bail};
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+/// A command alias, either a command line split on whitespace or a list of arguments.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum TomlAlias {
+    Command(String),
+    Args(Vec<String>),
+}
+
+impl TomlAlias {
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            Self::Command(command) => command.split_whitespace().map(String::from).collect(),
+            Self::Args(args) => args.clone(),
+        }
+    }
+}
+
+/// The `[alias]` table of the kelpie configuration, like `b = "build"` or
+/// `rr = ["run", "--release"]`.
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    aliases: BTreeMap<String, Vec<String>>,
+    origins: BTreeMap<String, String>,
+}
+
+impl Aliases {
+    /// The `[alias]` table of the merged configuration.
+    pub fn new(config: &KelpieConfig) -> Self {
+        let aliases = config
+            .alias
+            .iter()
+            .map(|(name, alias)| (name.clone(), alias.args()))
+            .collect();
+        Self {
+            aliases,
+            origins: config.alias_origins.clone(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.aliases.get(name).map(Vec::as_slice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
+        self.aliases.iter()
+    }
+
+    /// Makes sure no alias is named like a command in `builtins`, which would never run it,
+    /// or is empty. Errors name where the alias was set.
+    pub fn check(&self, builtins: &[&str]) -> Result<()> {
+        for (name, expansion) in &self.aliases {
+            let problem = if builtins.contains(&name.as_str()) {
+                "shadows the built-in command of the same name"
+            } else if expansion.is_empty() {
+                "is empty"
+            } else {
+                continue;
+            };
+
+            match self.origins.get(name) {
+                Some(origin) => bail!("alias `{}` in {} {}", name, origin, problem),
+                None => bail!("alias `{}` {}", name, problem),
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces an alias used as the command in `args`, the arguments of the process, with what
+    /// it stands for, until the command isn't an alias anymore. Aliases can't end up expanding
+    /// themselves.
+    pub fn expand(&self, mut args: Vec<OsString>) -> Result<Vec<OsString>> {
+        // `--config` is the only global flag before the command taking a value
+        let mut index = 1;
+        while let Some(arg) = args.get(index).map(|arg| arg.to_string_lossy()) {
+            if arg == "--config" {
+                index += 2;
+            } else if arg.starts_with('-') {
+                index += 1;
+            } else {
+                break;
+            }
+        }
+        if index >= args.len() {
+            return Ok(args);
+        }
+
+        let mut expanded: Vec<String> = vec![];
+        while let Some(expansion) = args[index].to_str().and_then(|name| self.get(name)) {
+            let name = args[index].to_string_lossy().into_owned();
+            if expanded.contains(&name) {
+                expanded.push(name.clone());
+                bail!("alias `{}` is recursive: {}", name, expanded.join(" -> "));
+            }
+            expanded.push(name);
+
+            args.splice(index..=index, expansion.iter().map(OsString::from));
+        }
+
+        Ok(args)
+    }
+}
@@ -0,0 +1,162 @@
+use crate::LibraryType;
+use anyhow::{Context, Result, bail};
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+
+/// Extension of the interface file the compiler writes next to a library artifact.
+pub const INTERFACE_EXTENSION: &str = "zrm";
+
+#[derive(Debug, Clone)]
+pub struct Compiler {
+    pub path: PathBuf,
+}
+
+impl Compiler {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn version(&self) -> Result<String> {
+        let output = Command::new(&self.path)
+            .arg("--version")
+            .output()
+            .with_context(|| format!("failed to run the compiler at {}", self.path.display()))?;
+        if !output.status.success() {
+            bail!(
+                "failed to get the version of the compiler at {}",
+                self.path.display()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Starts an invocation compiling `entrypoint` as the package `name`.
+    pub fn invocation(&self, name: &str, entrypoint: &Path) -> Invocation {
+        let mut cmd = Command::new(&self.path);
+        cmd.arg("--name").arg(name).arg(entrypoint);
+        Invocation { cmd }
+    }
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        // todo: temporarily
+        let path = if cfg!(windows) {
+            "../../zirael/target/debug/zirael.exe"
+        } else {
+            "../../zirael/target/debug/zirael"
+        };
+        Self::new(PathBuf::from(path))
+    }
+}
+
+#[derive(Debug)]
+pub struct Invocation {
+    cmd: Command,
+}
+
+impl Invocation {
+    pub fn mode(mut self, mode: &str) -> Self {
+        self.cmd.args(["--mode", mode]);
+        self
+    }
+
+    pub fn output(mut self, dir: &Path) -> Self {
+        self.cmd.arg("--output").arg(dir);
+        self
+    }
+
+    pub fn target(mut self, triple: &str) -> Self {
+        self.cmd.args(["--target", triple]);
+        self
+    }
+
+    /// Analyzes the package without generating code, only the interface is written.
+    pub fn check(mut self) -> Self {
+        self.cmd.arg("--check");
+        self
+    }
+
+    /// Writes HTML documentation of the package into the output directory instead of compiling
+    /// it.
+    pub fn doc(mut self) -> Self {
+        self.cmd.arg("--doc");
+        self
+    }
+
+    /// Links items of the dependency `name` to its documentation at `url`.
+    pub fn doc_link(mut self, name: &str, url: &str) -> Self {
+        self.cmd.arg("--doc-link").arg(format!("{name}={url}"));
+        self
+    }
+
+    /// Compiles a test harness running the tests found in the sources.
+    pub fn test(mut self) -> Self {
+        self.cmd.arg("--test");
+        self
+    }
+
+    /// Compiles a harness running the benchmarks found in the sources.
+    pub fn bench(mut self) -> Self {
+        self.cmd.arg("--bench");
+        self
+    }
+
+    pub fn lib_type(mut self, lib_type: LibraryType) -> Self {
+        self.cmd.arg("--lib").arg(lib_type.as_str());
+        self
+    }
+
+    /// Links against a dependency that was already compiled to `artifact`.
+    pub fn dependency(mut self, name: &str, artifact: &Path) -> Self {
+        self.cmd
+            .arg("-d")
+            .arg(format!("{}={}", name, artifact.display()));
+        self
+    }
+
+    pub fn args<S: AsRef<OsStr>>(mut self, args: &[S]) -> Self {
+        self.cmd.args(args);
+        self
+    }
+
+    pub fn command(self) -> Command {
+        self.cmd
+    }
+}
+
+/// File name of the artifact the compiler produces for `name`, `None` meaning a binary.
+pub fn artifact_file_name(name: &str, lib_type: Option<LibraryType>) -> String {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX, EXE_SUFFIX};
+
+    match lib_type {
+        None => format!("{name}{EXE_SUFFIX}"),
+        Some(LibraryType::Dynamic) => format!("{DLL_PREFIX}{name}{DLL_SUFFIX}"),
+        Some(LibraryType::Static) if cfg!(windows) => format!("{name}.lib"),
+        Some(LibraryType::Static) => format!("lib{name}.a"),
+    }
+}
+
+/// Runs `cmd`, forwarding its output line by line so the output of commands running in parallel
+/// doesn't get mixed up within a line.
+pub fn run_with_line_output(cmd: &mut Command) -> Result<ExitStatus> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                eprintln!("{line}");
+            }
+        });
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            println!("{line}");
+        }
+    });
+
+    Ok(child.wait()?)
+}
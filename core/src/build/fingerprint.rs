@@ -0,0 +1,277 @@
+use crate::{CONFIG_FILE, Package, StableHasher};
+use anyhow::Result;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Everything that affects the output of compiling a package. A package whose fingerprint matches
+/// the one stored by the previous build doesn't need to be compiled again.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub hash: String,
+    pub profile: String,
+    pub compiler: String,
+    pub flags: Vec<String>,
+    pub manifest: String,
+    /// Hashes of the files under the package root, keyed by their relative path.
+    pub sources: BTreeMap<String, String>,
+    /// Fingerprint hashes of the dependencies, keyed by `name@version`.
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl Fingerprint {
+    /// Hashes the package sources, skipping hidden directories, `target/`, nested packages and
+    /// anything under `exclude`.
+    pub fn compute(
+        package: &Package,
+        profile: &str,
+        compiler: &str,
+        flags: Vec<String>,
+        dependencies: BTreeMap<String, String>,
+        exclude: &[&Path],
+    ) -> Result<Self> {
+        let mut sources = BTreeMap::new();
+        hash_sources(
+            &package.root_path,
+            &package.root_path,
+            exclude,
+            &mut sources,
+        )?;
+
+        let manifest = hash_bytes(&fs_err::read(package.root_path.join(CONFIG_FILE))?);
+
+        let mut fingerprint = Self {
+            hash: String::new(),
+            profile: profile.to_string(),
+            compiler: compiler.to_string(),
+            flags,
+            manifest,
+            sources,
+            dependencies,
+        };
+
+        let mut hasher = StableHasher::new();
+        hasher
+            .update(&fingerprint.profile)
+            .update(&fingerprint.compiler)
+            .update(fingerprint.flags.len().to_le_bytes());
+        for flag in &fingerprint.flags {
+            hasher.update(flag);
+        }
+        hasher.update(&fingerprint.manifest);
+        for map in [&fingerprint.sources, &fingerprint.dependencies] {
+            hasher.update(map.len().to_le_bytes());
+            for (key, value) in map {
+                hasher.update(key).update(value);
+            }
+        }
+        fingerprint.hash = format!("{:016x}", hasher.finish());
+
+        Ok(fingerprint)
+    }
+
+    /// Name of the file the fingerprint of `package` is stored in, which tells apart versions of
+    /// the same package.
+    pub fn file_name(package: &Package) -> String {
+        format!("{}-{}.toml", package.name, package.version)
+    }
+
+    /// Returns `None` when there's no stored fingerprint or it can't be read.
+    pub fn load(path: &Path) -> Option<Self> {
+        let contents = fs_err::read_to_string(path).ok()?;
+        toml::from_str(&contents).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs_err::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Explains why a package with this fingerprint has to be compiled again, `None` if it's
+    /// fresh.
+    pub fn dirty_reason(&self, previous: Option<&Self>) -> Option<String> {
+        let Some(previous) = previous else {
+            return Some("it has never been built".to_string());
+        };
+
+        if self.hash == previous.hash {
+            return None;
+        }
+
+        if self.profile != previous.profile {
+            return Some(format!(
+                "the profile changed from {} to {}",
+                previous.profile, self.profile
+            ));
+        }
+        if self.compiler != previous.compiler {
+            return Some("the compiler version changed".to_string());
+        }
+        if self.flags != previous.flags {
+            return Some(format!(
+                "the flags changed from `{}` to `{}`",
+                previous.flags.join(" "),
+                self.flags.join(" ")
+            ));
+        }
+        if self.manifest != previous.manifest {
+            return Some(format!("{CONFIG_FILE} changed"));
+        }
+        if let Some(reason) = diff(&previous.sources, &self.sources, "file") {
+            return Some(reason);
+        }
+        if let Some(reason) = diff(&previous.dependencies, &self.dependencies, "dependency") {
+            return Some(reason);
+        }
+
+        Some("the fingerprint changed".to_string())
+    }
+}
+
+fn diff(
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+    what: &str,
+) -> Option<String> {
+    for (name, hash) in current {
+        match previous.get(name) {
+            None => return Some(format!("{what} {name} was added")),
+            Some(previous) if previous != hash => return Some(format!("{what} {name} changed")),
+            _ => {}
+        }
+    }
+
+    previous
+        .keys()
+        .find(|name| !current.contains_key(*name))
+        .map(|name| format!("{what} {name} was removed"))
+}
+
+fn hash_sources(
+    root: &Path,
+    dir: &Path,
+    exclude: &[&Path],
+    sources: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    let mut entries = fs_err::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+
+        if entry.file_type()?.is_dir() {
+            let skip = file_name.starts_with('.')
+                || file_name == "target"
+                || exclude.iter().any(|exclude| path.starts_with(exclude))
+                || path.join(CONFIG_FILE).exists();
+            if !skip {
+                hash_sources(root, &path, exclude, sources)?;
+            }
+        } else if dir != root || file_name != CONFIG_FILE {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            sources.insert(relative, hash_bytes(&fs_err::read(&path)?));
+        }
+    }
+
+    Ok(())
+}
+
+fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:016x}", StableHasher::new().update(bytes).finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint() -> Fingerprint {
+        Fingerprint {
+            hash: "1".to_string(),
+            profile: "release".to_string(),
+            compiler: "zc 1.0".to_string(),
+            flags: vec!["-O3".to_string()],
+            manifest: "m".to_string(),
+            sources: BTreeMap::from([
+                ("src/lib.zr".to_string(), "a".to_string()),
+                ("src/util.zr".to_string(), "b".to_string()),
+            ]),
+            dependencies: BTreeMap::from([("b@0.1.0".to_string(), "c".to_string())]),
+        }
+    }
+
+    /// Why `fingerprint()` changed by `change` is dirty.
+    fn reason(change: impl FnOnce(&mut Fingerprint)) -> Option<String> {
+        let mut current = fingerprint();
+        change(&mut current);
+        current.hash = "2".to_string();
+        current.dirty_reason(Some(&fingerprint()))
+    }
+
+    #[test]
+    fn unchanged_fingerprint_is_fresh() {
+        assert_eq!(fingerprint().dirty_reason(Some(&fingerprint())), None);
+    }
+
+    #[test]
+    fn dirty_reasons() {
+        assert_eq!(
+            fingerprint().dirty_reason(None).as_deref(),
+            Some("it has never been built")
+        );
+        assert_eq!(
+            reason(|f| f.profile = "debug".to_string()).as_deref(),
+            Some("the profile changed from release to debug")
+        );
+        assert_eq!(
+            reason(|f| f.compiler = "zc 2.0".to_string()).as_deref(),
+            Some("the compiler version changed")
+        );
+        assert_eq!(
+            reason(|f| f.flags.push("-g".to_string())).as_deref(),
+            Some("the flags changed from `-O3` to `-O3 -g`")
+        );
+        assert_eq!(
+            reason(|f| f.manifest = "n".to_string()).as_deref(),
+            Some("config.toml changed")
+        );
+        assert_eq!(
+            reason(|f| {
+                f.dependencies
+                    .insert("b@0.1.0".to_string(), "d".to_string());
+            })
+            .as_deref(),
+            Some("dependency b@0.1.0 changed")
+        );
+        assert_eq!(reason(|_| {}).as_deref(), Some("the fingerprint changed"));
+    }
+
+    #[test]
+    fn diff_names_the_first_difference() {
+        let previous = fingerprint().sources;
+
+        let mut added = previous.clone();
+        added.insert("src/new.zr".to_string(), "c".to_string());
+        assert_eq!(
+            diff(&previous, &added, "file").as_deref(),
+            Some("file src/new.zr was added")
+        );
+
+        let mut changed = previous.clone();
+        changed.insert("src/util.zr".to_string(), "c".to_string());
+        assert_eq!(
+            diff(&previous, &changed, "file").as_deref(),
+            Some("file src/util.zr changed")
+        );
+
+        let mut removed = previous.clone();
+        removed.remove("src/lib.zr");
+        assert_eq!(
+            diff(&previous, &removed, "file").as_deref(),
+            Some("file src/lib.zr was removed")
+        );
+
+        assert_eq!(diff(&previous, &previous, "file"), None);
+    }
+}
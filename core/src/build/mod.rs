@@ -0,0 +1,1148 @@
+mod compiler;
+mod fingerprint;
+mod scheduler;
+
+pub use compiler::*;
+pub use fingerprint::*;
+pub use scheduler::*;
+
+use crate::{
+    CONFIG_FILE, DependencyGraph, DocTest, KelpieContext, LibraryType, Package, PackageId, Project,
+    ProjectId, TEST_EXTENSION, TargetLayout, TestBinary, TestOutcome, TestResult, TestTargetKind,
+    TomlConfig, discover_bench_targets, discover_test_targets, workspace_root,
+};
+use anyhow::{Result, anyhow, bail};
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use zirael_utils::prelude::PackageType;
+
+pub const ZFLAGS_ENV: &str = "KELPIE_ZFLAGS";
+
+/// Output directory and library kinds of every package the build needs.
+/// Where each package is compiled to: the directory, the stem of its files and its library
+/// kinds.
+type Planned = HashMap<PackageId, (PathBuf, String, Vec<Option<LibraryType>>)>;
+
+/// Artifacts a unit is compiled against and the fingerprints of their packages, by name.
+type Dependencies = (Vec<(String, PathBuf)>, BTreeMap<String, String>);
+
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    pub mode: &'static str,
+    /// Library kinds requested on the command line, overriding the manifest's `lib-type` of the
+    /// packages being built. Dependencies always use their own.
+    pub lib_types: Option<Vec<LibraryType>>,
+    /// Target triple to cross-compile for.
+    pub target: Option<String>,
+    /// Overrides the target directory, see [`TargetLayout::for_project`].
+    pub target_dir: Option<PathBuf>,
+    /// `build.target-dir` from kelpie's configuration, below `target_dir`.
+    pub configured_target_dir: Option<PathBuf>,
+    /// Number of packages compiled in parallel, defaults to the number of CPUs.
+    pub jobs: Option<usize>,
+    /// Compiler executable, instead of the default one.
+    pub compiler: Option<PathBuf>,
+    pub keep_going: bool,
+    /// Extra compiler arguments for the packages being built, but not their dependencies.
+    pub args: Vec<String>,
+    /// Only analyze the packages, producing their interfaces but no code.
+    pub check: bool,
+    /// Also compile the test harnesses of the packages being built, with their
+    /// dev-dependencies.
+    pub test: bool,
+    /// Also compile the benchmarks of the packages being built, with their dev-dependencies.
+    pub bench: bool,
+    /// Also compile the dev-dependencies the doc tests of the packages being built use, see
+    /// [`Builder::run_doc_test`].
+    pub doc_tests: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum UnitId {
+    Package(PackageId),
+    /// The harness at this index of [`BuildOutput::tests`].
+    Test(usize),
+}
+
+/// Something that has to be compiled, with everything worked out up front so it can be
+/// compiled on any thread.
+struct CompileUnit<'a> {
+    package: &'a Package,
+    /// Name of the artifact, the package name except for harnesses.
+    name: String,
+    entrypoint: PathBuf,
+    harness: Option<TestTargetKind>,
+    /// Which harness of the package this is, shown when compiling it.
+    label: Option<String>,
+    output_dir: PathBuf,
+    /// Stem of the files in `output_dir`, `name` unless the unit is a dependency.
+    file_stem: String,
+    lib_types: Vec<Option<LibraryType>>,
+    dependencies: Vec<(String, PathBuf)>,
+    args: Vec<String>,
+    fingerprint: Fingerprint,
+    fingerprint_path: PathBuf,
+}
+
+/// A file produced by compiling a package.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub package: PackageId,
+    /// `None` for binaries.
+    pub lib_type: Option<LibraryType>,
+    pub path: PathBuf,
+    /// Interface file emitted alongside libraries, used when compiling dependents.
+    pub interface: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct BuildOutput {
+    pub artifacts: HashMap<PackageId, Vec<Artifact>>,
+    /// Test and bench harnesses of the packages that were built, in the order they were given.
+    pub tests: Vec<TestBinary>,
+}
+
+impl BuildOutput {
+    pub fn artifacts(&self, package: PackageId) -> &[Artifact] {
+        self.artifacts.get(&package).map_or(&[], Vec::as_slice)
+    }
+}
+
+pub struct Builder<'a> {
+    ctx: &'a KelpieContext,
+    project_id: ProjectId,
+    compiler: Compiler,
+    options: BuildOptions,
+    layout: TargetLayout,
+    /// Flags from the profile, `[build] zflags` or `KELPIE_ZFLAGS`, passed to every package.
+    flags: Vec<String>,
+}
+
+impl<'a> Builder<'a> {
+    pub fn new(
+        ctx: &'a KelpieContext,
+        project_id: ProjectId,
+        options: BuildOptions,
+    ) -> Result<Self> {
+        let project = ctx
+            .get_project(project_id)
+            .ok_or_else(|| anyhow!("Failed to get project"))?;
+        let layout = TargetLayout::for_project(
+            project,
+            options.target_dir.as_deref(),
+            options.configured_target_dir.as_deref(),
+            options.target.as_deref(),
+            options.mode,
+        )?;
+        let flags = configured_flags(project, options.mode)?;
+
+        Ok(Self {
+            ctx,
+            project_id,
+            compiler: options
+                .compiler
+                .clone()
+                .map(Compiler::new)
+                .unwrap_or_default(),
+            options,
+            layout,
+            flags,
+        })
+    }
+
+    pub fn layout(&self) -> &TargetLayout {
+        &self.layout
+    }
+
+    pub fn compiler(mut self, compiler: Compiler) -> Self {
+        self.compiler = compiler;
+        self
+    }
+
+    /// Compiles `targets` and everything they depend on, each package exactly once and
+    /// dependencies before their dependents. Independent packages are compiled in parallel.
+    pub fn build(&self, targets: &[PackageId]) -> Result<BuildOutput> {
+        let mut graph = DependencyGraph::new();
+        graph.build_from_project(self.ctx, self.project_id)?;
+
+        let mut needed: HashSet<PackageId> = targets.iter().copied().collect();
+        let mut dev_dependencies = HashMap::new();
+        for &target in targets {
+            needed.extend(graph.transitive_dependencies(target));
+
+            if self.options.test || self.options.bench || self.options.doc_tests {
+                let dev = graph.add_dev_dependencies(self.ctx, target);
+                for &dependency in &dev {
+                    needed.insert(dependency);
+                    needed.extend(graph.transitive_dependencies(dependency));
+                }
+                dev_dependencies.insert(target, dev);
+            }
+        }
+
+        self.layout.prepare()?;
+
+        let mut compiler_version = None;
+        let mut fingerprints: HashMap<PackageId, String> = HashMap::new();
+        let mut planned: Planned = HashMap::new();
+        let mut units = vec![];
+        let mut labels = HashMap::new();
+
+        for package_id in graph.get_compilation_order()? {
+            if !needed.contains(&package_id) {
+                continue;
+            }
+
+            let package = self.package(package_id)?;
+            let is_target = targets.contains(&package_id);
+            let output_dir = if self.options.check {
+                self.layout.check()
+            } else if is_target {
+                self.layout.dest().to_path_buf()
+            } else {
+                self.layout.deps()
+            };
+
+            let (dependencies, dependency_fingerprints) = self.dependencies(
+                package,
+                graph.transitive_dependencies(package_id),
+                &planned,
+                &fingerprints,
+            )?;
+
+            // dependencies are named after their version too, as two versions of a package can
+            // be compiled into the same directory
+            let file_stem = if is_target {
+                package.name.clone()
+            } else {
+                format!("{}-{}", package.name, package.version)
+            };
+            let lib_types = self.lib_types(package, is_target)?;
+            let artifacts = self.artifacts(package_id, &file_stem, &lib_types, &output_dir);
+
+            let compiler_version = match &compiler_version {
+                Some(version) => version,
+                None => compiler_version.insert(self.compiler.version()?),
+            };
+            let fingerprint = Fingerprint::compute(
+                package,
+                self.options.mode,
+                compiler_version,
+                self.flags(&lib_types, is_target),
+                dependency_fingerprints,
+                &[self.layout.target_dir()],
+            )?;
+            let fingerprint_path = self.fingerprint_dir().join(Fingerprint::file_name(package));
+
+            let paths: Vec<_> = artifacts
+                .into_iter()
+                .map(|artifact| artifact.path)
+                .collect();
+            let dirty_reason = dirty_reason(&fingerprint, &fingerprint_path, &paths);
+
+            fingerprints.insert(package_id, fingerprint.hash.clone());
+            planned.insert(
+                package_id,
+                (output_dir.clone(), file_stem.clone(), lib_types.clone()),
+            );
+
+            let Some(dirty_reason) = dirty_reason else {
+                info!("Fresh {} v{}", package.name, package.version);
+                continue;
+            };
+
+            debug!(
+                "{} v{} is dirty because {}",
+                package.name, package.version, dirty_reason
+            );
+
+            let id = UnitId::Package(package_id);
+            labels.insert(id, format!("{} v{}", package.name, package.version));
+            units.push((
+                id,
+                graph
+                    .dependencies(package_id)
+                    .into_iter()
+                    .map(UnitId::Package)
+                    .collect(),
+                CompileUnit {
+                    package,
+                    name: package.name.clone(),
+                    entrypoint: package.full_entrypoint_path(),
+                    harness: None,
+                    label: None,
+                    args: self.extra_args(is_target),
+                    output_dir,
+                    file_stem,
+                    lib_types,
+                    dependencies,
+                    fingerprint,
+                    fingerprint_path,
+                },
+            ));
+        }
+
+        let mut output = BuildOutput::default();
+        if self.options.test || self.options.bench {
+            let compiler_version = match compiler_version {
+                Some(version) => version,
+                None => self.compiler.version()?,
+            };
+
+            for &target in targets {
+                self.plan_harnesses(
+                    target,
+                    &graph,
+                    &dev_dependencies[&target],
+                    &compiler_version,
+                    (&planned, &fingerprints),
+                    &mut output.tests,
+                    &mut units,
+                    &mut labels,
+                )?;
+            }
+        }
+
+        let outcome = self.scheduler().run(units, |unit| self.compile(unit));
+        report(outcome, &labels)?;
+
+        for (package_id, (output_dir, file_stem, lib_types)) in planned {
+            output.artifacts.insert(
+                package_id,
+                self.artifacts(package_id, &file_stem, &lib_types, &output_dir),
+            );
+        }
+
+        Ok(output)
+    }
+
+    /// Adds a unit for each test or bench harness of `target` that has to be compiled.
+    /// Harnesses link against the package, unless it's a binary, its dependencies and its
+    /// dev-dependencies.
+    #[allow(clippy::too_many_arguments)]
+    fn plan_harnesses(
+        &self,
+        target: PackageId,
+        graph: &DependencyGraph,
+        dev_dependencies: &[PackageId],
+        compiler_version: &str,
+        (planned, fingerprints): (&Planned, &HashMap<PackageId, String>),
+        tests: &mut Vec<TestBinary>,
+        units: &mut Vec<(UnitId, Vec<UnitId>, CompileUnit<'a>)>,
+        labels: &mut HashMap<UnitId, String>,
+    ) -> Result<()> {
+        let package = self.package(target)?;
+
+        let mut uses = graph.transitive_dependencies(target);
+        for &dependency in dev_dependencies {
+            uses.push(dependency);
+            uses.extend(graph.transitive_dependencies(dependency));
+        }
+        let mut seen = HashSet::new();
+        uses.retain(|id| seen.insert(*id));
+
+        let mut harnesses = vec![];
+        if self.options.test {
+            harnesses.extend(discover_test_targets(target, package)?);
+        }
+        if self.options.bench {
+            harnesses.extend(discover_bench_targets(target, package)?);
+        }
+
+        for test_target in harnesses {
+            let (output_dir, fingerprint_dir) = match test_target.kind {
+                TestTargetKind::Bench => (
+                    self.layout.benches(),
+                    self.fingerprint_dir().join("benches"),
+                ),
+                _ => (self.layout.tests(), self.fingerprint_dir().join("tests")),
+            };
+            let output_dir = output_dir.join(&package.name);
+            let fingerprint_dir = fingerprint_dir.join(&package.name);
+            fs_err::create_dir_all(&output_dir)?;
+            fs_err::create_dir_all(&fingerprint_dir)?;
+
+            // unit tests are compiled from the package sources, the other harnesses use it like
+            // any other dependency
+            let mut dependencies = uses.clone();
+            if test_target.kind != TestTargetKind::Unit && package.ty == PackageType::Library {
+                dependencies.insert(0, target);
+            }
+
+            let (artifacts, dependency_fingerprints) =
+                self.dependencies(package, dependencies.iter().copied(), planned, fingerprints)?;
+
+            let name = test_target.binary_name(package);
+            let path = output_dir.join(artifact_file_name(&name, None));
+
+            let mut flags = self.flags(&[], true);
+            flags.extend([
+                harness_flag(test_target.kind).to_string(),
+                test_target.source.display().to_string(),
+            ]);
+            let fingerprint = Fingerprint::compute(
+                package,
+                self.options.mode,
+                compiler_version,
+                flags,
+                dependency_fingerprints,
+                &[self.layout.target_dir()],
+            )?;
+            let fingerprint_path = fingerprint_dir.join(format!("{name}.toml"));
+
+            let index = tests.len();
+            tests.push(TestBinary {
+                target: test_target.clone(),
+                path: path.clone(),
+            });
+
+            let label = match test_target.kind {
+                TestTargetKind::Unit => "unit tests".to_string(),
+                TestTargetKind::Integration => format!("test \"{}\"", test_target.name),
+                TestTargetKind::Bench => format!("bench \"{}\"", test_target.name),
+                TestTargetKind::Doc => "doc tests".to_string(),
+            };
+            let Some(dirty_reason) = dirty_reason(&fingerprint, &fingerprint_path, &[path]) else {
+                continue;
+            };
+
+            debug!(
+                "{} v{} ({}) is dirty because {}",
+                package.name, package.version, label, dirty_reason
+            );
+
+            let id = UnitId::Test(index);
+            labels.insert(
+                id,
+                format!("{} v{} ({})", package.name, package.version, label),
+            );
+            units.push((
+                id,
+                dependencies.into_iter().map(UnitId::Package).collect(),
+                CompileUnit {
+                    package,
+                    name: name.clone(),
+                    entrypoint: test_target.source,
+                    harness: Some(test_target.kind),
+                    label: Some(label),
+                    args: self.extra_args(true),
+                    output_dir,
+                    file_stem: name.clone(),
+                    lib_types: vec![None],
+                    dependencies: artifacts,
+                    fingerprint,
+                    fingerprint_path,
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Artifacts the doc tests of `package_id` link against, from a build of the package with
+    /// `doc_tests` set: the package itself, its dependencies and its dev-dependencies.
+    pub fn doc_test_dependencies(
+        &self,
+        package_id: PackageId,
+        output: &BuildOutput,
+    ) -> Result<Vec<(String, PathBuf)>> {
+        let mut graph = DependencyGraph::new();
+        graph.build_from_project(self.ctx, self.project_id)?;
+
+        let mut uses = vec![package_id];
+        uses.extend(graph.transitive_dependencies(package_id));
+        for dependency in graph.add_dev_dependencies(self.ctx, package_id) {
+            uses.push(dependency);
+            uses.extend(graph.transitive_dependencies(dependency));
+        }
+
+        let mut seen = HashSet::new();
+        let mut dependencies = vec![];
+        for id in uses.into_iter().filter(|id| seen.insert(*id)) {
+            let package = self.package(id)?;
+            let artifact = output.artifacts(id).first().ok_or_else(|| {
+                anyhow!("{} has to be built before running doc tests", package.name)
+            })?;
+            dependencies.push((package.name.clone(), artifact.path.clone()));
+        }
+        Ok(dependencies)
+    }
+
+    /// Compiles the doc test `test` of `package_id` into a binary and runs it from the package
+    /// root, unless it's `ignore` or `no_run`. `index` tells the doc tests of a package apart.
+    pub fn run_doc_test(
+        &self,
+        package_id: PackageId,
+        test: &DocTest,
+        index: usize,
+        dependencies: &[(String, PathBuf)],
+    ) -> Result<TestResult> {
+        let mut result = TestResult {
+            name: test.name.clone(),
+            outcome: TestOutcome::Ignored,
+            duration: Duration::ZERO,
+            output: String::new(),
+        };
+        if test.ignore {
+            return Ok(result);
+        }
+
+        let package = self.package(package_id)?;
+        let dir = self.layout.doc_tests().join(&package.name);
+        fs_err::create_dir_all(&dir)?;
+
+        let name = format!("{}_doctest_{}", package.name, index);
+        let source = dir.join(&name).with_extension(TEST_EXTENSION);
+        fs_err::write(&source, test.program())?;
+
+        let mut invocation = self
+            .compiler
+            .invocation(&name, &source)
+            .mode(self.options.mode)
+            .output(&dir);
+        if let Some(target) = &self.options.target {
+            invocation = invocation.target(target);
+        }
+        for (name, artifact) in dependencies {
+            invocation = invocation.dependency(name, artifact);
+        }
+        let mut cmd = invocation.args(&self.flags).command();
+        debug!("{:?}", cmd);
+
+        let start = Instant::now();
+        let compiled = cmd.output()?;
+        result
+            .output
+            .push_str(&String::from_utf8_lossy(&compiled.stdout));
+        result
+            .output
+            .push_str(&String::from_utf8_lossy(&compiled.stderr));
+
+        let succeeded = if !compiled.status.success() {
+            false
+        } else if test.no_run {
+            true
+        } else {
+            let mut cmd = Command::new(dir.join(artifact_file_name(&name, None)));
+            cmd.current_dir(&package.root_path);
+            debug!("{:?}", cmd);
+
+            let ran = cmd.output()?;
+            result
+                .output
+                .push_str(&String::from_utf8_lossy(&ran.stdout));
+            result
+                .output
+                .push_str(&String::from_utf8_lossy(&ran.stderr));
+            ran.status.success()
+        };
+        result.duration = start.elapsed();
+
+        result.outcome = if succeeded != test.should_fail {
+            TestOutcome::Passed
+        } else {
+            if succeeded {
+                result
+                    .output
+                    .push_str("the example succeeded but should have failed\n");
+            }
+            TestOutcome::Failed
+        };
+        Ok(result)
+    }
+
+    fn dependencies(
+        &self,
+        package: &Package,
+        dependencies: impl IntoIterator<Item = PackageId>,
+        planned: &Planned,
+        fingerprints: &HashMap<PackageId, String>,
+    ) -> Result<Dependencies> {
+        let mut artifacts = vec![];
+        let mut dependency_fingerprints = BTreeMap::new();
+
+        for dependency in dependencies {
+            let dependency_package = self.package(dependency)?;
+            let (dependency_dir, file_stem, dependency_lib_types) = &planned[&dependency];
+            let lib_type = dependency_lib_types.first().ok_or_else(|| {
+                anyhow!(
+                    "dependency {} of {} has no artifact",
+                    dependency_package.name,
+                    package.name
+                )
+            })?;
+
+            artifacts.push((
+                dependency_package.name.clone(),
+                self.artifact_path(file_stem, *lib_type, dependency_dir),
+            ));
+            dependency_fingerprints.insert(
+                format!("{}@{}", dependency_package.name, dependency_package.version),
+                fingerprints[&dependency].clone(),
+            );
+        }
+
+        Ok((artifacts, dependency_fingerprints))
+    }
+
+    fn scheduler(&self) -> Scheduler {
+        Scheduler::new(
+            self.options.jobs.unwrap_or_else(Scheduler::default_jobs),
+            self.options.keep_going,
+        )
+    }
+
+    fn compile(&self, unit: CompileUnit<'_>) -> Result<()> {
+        let package = unit.package;
+        let action = if self.options.check {
+            "Checking"
+        } else {
+            "Compiling"
+        };
+        match &unit.label {
+            Some(label) => info!(
+                "{} {} v{} ({})",
+                action, package.name, package.version, label
+            ),
+            None => info!("{} {} v{}", action, package.name, package.version),
+        }
+
+        if unit.fingerprint_path.exists() {
+            fs_err::remove_file(&unit.fingerprint_path)?;
+        }
+
+        // the compiler names its files after the package, so they are renamed once compiled
+        let compiler_dir = if unit.file_stem == unit.name {
+            unit.output_dir.clone()
+        } else {
+            self.layout.incremental().join(&unit.file_stem)
+        };
+
+        for &lib_type in &unit.lib_types {
+            let mut invocation = self
+                .compiler
+                .invocation(&unit.name, &unit.entrypoint)
+                .mode(self.options.mode)
+                .output(&compiler_dir);
+
+            if self.options.check {
+                invocation = invocation.check();
+            }
+
+            match unit.harness {
+                Some(TestTargetKind::Bench) => invocation = invocation.bench(),
+                Some(_) => invocation = invocation.test(),
+                None => {}
+            }
+
+            if let Some(target) = &self.options.target {
+                invocation = invocation.target(target);
+            }
+
+            if let Some(lib_type) = lib_type {
+                invocation = invocation.lib_type(lib_type);
+            }
+
+            for (name, artifact) in &unit.dependencies {
+                invocation = invocation.dependency(name, artifact);
+            }
+            invocation = invocation.args(&unit.args);
+
+            let mut cmd = invocation.command();
+            debug!("{:?}", cmd);
+
+            let status = run_with_line_output(&mut cmd)?;
+            if !status.success() {
+                match &unit.label {
+                    Some(label) => bail!("failed to compile {} of package {}", label, package.name),
+                    None => bail!("failed to compile package {}", package.name),
+                }
+            }
+
+            if compiler_dir != unit.output_dir {
+                let compiled = self.artifact_path(&unit.name, lib_type, &compiler_dir);
+                let artifact = self.artifact_path(&unit.file_stem, lib_type, &unit.output_dir);
+                if compiled.exists() {
+                    fs_err::rename(compiled, artifact)?;
+                }
+            }
+        }
+
+        let compiled = interface_path(&unit.name, &compiler_dir);
+        if compiler_dir != unit.output_dir && compiled.exists() {
+            fs_err::rename(compiled, interface_path(&unit.file_stem, &unit.output_dir))?;
+        }
+
+        unit.fingerprint.save(&unit.fingerprint_path)
+    }
+
+    /// Flags affecting the compiler output, other than the dependencies.
+    fn flags(&self, lib_types: &[Option<LibraryType>], is_target: bool) -> Vec<String> {
+        let mut flags = vec!["--mode".to_string(), self.options.mode.to_string()];
+        if self.options.check {
+            flags.push("--check".to_string());
+        }
+        if let Some(target) = &self.options.target {
+            flags.extend(["--target".to_string(), target.clone()]);
+        }
+        for lib_type in lib_types.iter().flatten() {
+            flags.extend(["--lib".to_string(), lib_type.as_str().to_string()]);
+        }
+        flags.extend(self.extra_args(is_target).iter().cloned());
+        flags
+    }
+
+    /// Arguments appended to every invocation compiling a package.
+    fn extra_args(&self, is_target: bool) -> Vec<String> {
+        let mut args = self.flags.clone();
+        if is_target {
+            args.extend(self.options.args.iter().cloned());
+        }
+        args
+    }
+
+    fn package(&self, package_id: PackageId) -> Result<&'a Package> {
+        self.ctx
+            .get_package(package_id)
+            .ok_or_else(|| anyhow!("Failed to get package"))
+    }
+
+    fn lib_types(&self, package: &Package, is_target: bool) -> Result<Vec<Option<LibraryType>>> {
+        let requested = self.options.lib_types.as_ref().filter(|_| is_target);
+
+        Ok(match package.ty {
+            PackageType::Binary => {
+                if requested.is_some() {
+                    bail!(
+                        "package {} is a binary and cannot be built as a library",
+                        package.name
+                    );
+                }
+                vec![None]
+            }
+            // the kind of library doesn't matter when checking, so check it once
+            PackageType::Library if self.options.check => requested
+                .unwrap_or(&package.lib_types)
+                .first()
+                .map(|&lib_type| vec![Some(lib_type)])
+                .unwrap_or_default(),
+            PackageType::Library => requested
+                .unwrap_or(&package.lib_types)
+                .iter()
+                .copied()
+                .map(Some)
+                .collect(),
+        })
+    }
+
+    fn fingerprint_dir(&self) -> PathBuf {
+        if self.options.check {
+            self.layout.check().join(".fingerprint")
+        } else {
+            self.layout.fingerprint()
+        }
+    }
+
+    /// Checking only produces the interface, which then takes the place of the artifact.
+    fn artifact_path(
+        &self,
+        file_stem: &str,
+        lib_type: Option<LibraryType>,
+        output_dir: &Path,
+    ) -> PathBuf {
+        if self.options.check {
+            interface_path(file_stem, output_dir)
+        } else {
+            output_dir.join(artifact_file_name(file_stem, lib_type))
+        }
+    }
+
+    fn artifacts(
+        &self,
+        package_id: PackageId,
+        file_stem: &str,
+        lib_types: &[Option<LibraryType>],
+        output_dir: &Path,
+    ) -> Vec<Artifact> {
+        let interface = interface_path(file_stem, output_dir);
+
+        lib_types
+            .iter()
+            .map(|&lib_type| Artifact {
+                package: package_id,
+                lib_type,
+                path: self.artifact_path(file_stem, lib_type, output_dir),
+                interface: (self.options.check || lib_type.is_some())
+                    .then(|| interface.clone())
+                    .filter(|interface| interface.exists()),
+            })
+            .collect()
+    }
+}
+
+fn harness_flag(kind: TestTargetKind) -> &'static str {
+    match kind {
+        TestTargetKind::Bench => "--bench",
+        TestTargetKind::Unit | TestTargetKind::Integration | TestTargetKind::Doc => "--test",
+    }
+}
+
+/// Why a unit has to be compiled again, if at all.
+fn dirty_reason(
+    fingerprint: &Fingerprint,
+    fingerprint_path: &Path,
+    artifacts: &[PathBuf],
+) -> Option<String> {
+    let previous = Fingerprint::load(fingerprint_path);
+    fingerprint.dirty_reason(previous.as_ref()).or_else(|| {
+        artifacts
+            .iter()
+            .find(|path| !path.exists())
+            .map(|path| format!("{} is missing", path.display()))
+    })
+}
+
+fn report(outcome: ScheduleOutcome<UnitId>, labels: &HashMap<UnitId, String>) -> Result<()> {
+    for id in &outcome.skipped {
+        warn!(
+            "Skipped {} because a dependency failed to compile",
+            labels[id]
+        );
+    }
+
+    let mut failed = outcome.failed;
+    match failed.len() {
+        0 => Ok(()),
+        1 => Err(failed.remove(0).1),
+        count => {
+            for (_, error) in &failed {
+                error!("{error:?}");
+            }
+
+            let packages = failed
+                .iter()
+                .filter(|(id, _)| matches!(id, UnitId::Package(_)))
+                .count();
+            let harnesses = count - packages;
+            let what = match (packages, harnesses) {
+                (_, 0) => format!("{packages} packages"),
+                (0, _) => format!("{harnesses} test harnesses"),
+                _ => format!(
+                    "{} and {}",
+                    plural(packages, "package", "packages"),
+                    plural(harnesses, "test harness", "test harnesses")
+                ),
+            };
+            bail!("failed to compile {}", what)
+        }
+    }
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+/// `<file_stem>.zrm`, not replacing what follows a dot in the version of a dependency.
+fn interface_path(file_stem: &str, output_dir: &Path) -> PathBuf {
+    output_dir.join(format!("{file_stem}.{INTERFACE_EXTENSION}"))
+}
+
+/// Flags from `[profile.<profile>] flags` followed by `KELPIE_ZFLAGS`, or `[build] zflags` when
+/// the variable isn't set, read from the root manifest.
+fn configured_flags(project: &Project, profile: &str) -> Result<Vec<String>> {
+    let toml = TomlConfig::from_path(workspace_root(project)?.join(CONFIG_FILE))?;
+
+    let mut flags = toml
+        .profile
+        .and_then(|mut profiles| profiles.remove(profile))
+        .and_then(|profile| profile.flags)
+        .unwrap_or_default();
+
+    match env::var(ZFLAGS_ENV) {
+        Ok(zflags) => flags.extend(zflags.split_whitespace().map(str::to_string)),
+        Err(_) => flags.extend(
+            toml.build
+                .and_then(|build| build.zflags)
+                .unwrap_or_default(),
+        ),
+    }
+
+    Ok(flags)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    fn options() -> BuildOptions {
+        BuildOptions {
+            mode: "release",
+            lib_types: None,
+            target: None,
+            target_dir: None,
+            configured_target_dir: None,
+            jobs: Some(1),
+            compiler: None,
+            keep_going: false,
+            args: vec![],
+            check: false,
+            test: false,
+            bench: false,
+            doc_tests: false,
+        }
+    }
+
+    /// `app` depends on the library `a`, which depends on the library `b`.
+    fn workspace(root_manifest: &str) -> Fixture {
+        Fixture::new(&[
+            ("config.toml", root_manifest),
+            (
+                "app/config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+                type = "bin"
+
+                [dependencies]
+                a = { version = "0.1.0", path = "../a" }
+                "#,
+            ),
+            ("app/src/main.zr", "fn main() {}"),
+            (
+                "a/config.toml",
+                r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                b = { version = "0.1.0", path = "../b" }
+                "#,
+            ),
+            ("a/src/lib.zr", ""),
+            (
+                "b/config.toml",
+                r#"
+                [package]
+                name = "b"
+                version = "0.1.0"
+                "#,
+            ),
+            ("b/src/lib.zr", ""),
+        ])
+    }
+
+    const MEMBERS: &str = r#"
+        [workspace]
+        members = ["app", "a", "b"]
+    "#;
+
+    fn build(
+        fixture: &Fixture,
+        ctx: &KelpieContext,
+        project_id: ProjectId,
+        options: BuildOptions,
+        targets: &[&str],
+    ) -> Result<BuildOutput> {
+        let targets: Vec<PackageId> = targets
+            .iter()
+            .map(|name| ctx.get_package_by_name(name).unwrap().id)
+            .collect();
+        Builder::new(ctx, project_id, options)?
+            .compiler(fixture.stub_compiler())
+            .build(&targets)
+    }
+
+    #[test]
+    fn compiles_dependencies_first_and_links_their_artifacts() {
+        let fixture = workspace(MEMBERS);
+        let (ctx, project_id) = fixture.load();
+        build(&fixture, &ctx, project_id, options(), &["app"]).unwrap();
+
+        let b = artifact_file_name("b-0.1.0", Some(LibraryType::Dynamic));
+        let a = artifact_file_name("a-0.1.0", Some(LibraryType::Dynamic));
+        assert_eq!(
+            fixture.compiler_log(),
+            [
+                "--name b <root>/b/src/lib.zr --mode release --output \
+                 <root>/target/release/incremental/b-0.1.0 --lib dynamic"
+                    .to_string(),
+                format!(
+                    "--name a <root>/a/src/lib.zr --mode release --output \
+                     <root>/target/release/incremental/a-0.1.0 --lib dynamic \
+                     -d b=<root>/target/release/deps/{b}"
+                ),
+                format!(
+                    "--name app <root>/app/src/main.zr --mode release --output \
+                     <root>/target/release -d a=<root>/target/release/deps/{a} \
+                     -d b=<root>/target/release/deps/{b}"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn artifacts_are_reported_where_the_compiler_wrote_them() {
+        let fixture = workspace(MEMBERS);
+        let (ctx, project_id) = fixture.load();
+        let output = build(&fixture, &ctx, project_id, options(), &["a"]).unwrap();
+
+        let a = ctx.get_package_by_name("a").unwrap().id;
+        let b = ctx.get_package_by_name("b").unwrap().id;
+        let release = fixture.root().join("target").join("release");
+        assert_eq!(
+            output.artifacts(a)[0].path,
+            release.join(artifact_file_name("a", Some(LibraryType::Dynamic)))
+        );
+        assert_eq!(
+            output.artifacts(b)[0].path,
+            release
+                .join("deps")
+                .join(artifact_file_name("b-0.1.0", Some(LibraryType::Dynamic)))
+        );
+        assert!(output.artifacts(a)[0].path.exists());
+        assert!(output.artifacts(b)[0].path.exists());
+    }
+
+    #[test]
+    fn extra_args_only_reach_the_packages_being_built() {
+        let fixture = workspace(
+            r#"
+            [workspace]
+            members = ["app", "a", "b"]
+
+            [build]
+            zflags = ["-Zall"]
+
+            [profile.release]
+            flags = ["-O3"]
+            "#,
+        );
+        let options = BuildOptions {
+            args: vec!["--emit".to_string(), "ir".to_string()],
+            ..options()
+        };
+        let (ctx, project_id) = fixture.load();
+        build(&fixture, &ctx, project_id, options, &["a"]).unwrap();
+
+        let log = fixture.compiler_log();
+        assert_eq!(log.len(), 2, "{log:?}");
+        assert!(log[0].starts_with("--name b "), "{}", log[0]);
+        assert!(log[0].ends_with(" -O3 -Zall"), "{}", log[0]);
+        assert!(log[1].starts_with("--name a "), "{}", log[1]);
+        assert!(log[1].ends_with(" -O3 -Zall --emit ir"), "{}", log[1]);
+    }
+
+    #[test]
+    fn versions_of_a_package_keep_their_own_fingerprint() {
+        let package = |name: &str, version: &str, dependency: &str| {
+            format!(
+                "[package]\nname = \"{name}\"\nversion = \"{version}\"\ntype = \"bin\"\n\n\
+                 [dependencies]\n{dependency}\n"
+            )
+        };
+        let app = package(
+            "app",
+            "0.1.0",
+            r#"dup = { version = "1.0.0", path = "../d1" }"#,
+        );
+        let tool = package(
+            "tool",
+            "0.1.0",
+            r#"dup = { version = "2.0.0", path = "../d2" }"#,
+        );
+        let fixture = Fixture::new(&[
+            (
+                "config.toml",
+                "[workspace]\nmembers = [\"app\", \"tool\"]\n",
+            ),
+            ("app/config.toml", &app),
+            ("app/src/main.zr", "fn main() {}"),
+            ("tool/config.toml", &tool),
+            ("tool/src/main.zr", "fn main() {}"),
+            (
+                "d1/config.toml",
+                "[package]\nname = \"dup\"\nversion = \"1.0.0\"\n",
+            ),
+            ("d1/src/lib.zr", ""),
+            (
+                "d2/config.toml",
+                "[package]\nname = \"dup\"\nversion = \"2.0.0\"\n",
+            ),
+            ("d2/src/lib.zr", ""),
+        ]);
+        let (ctx, project_id) = fixture.load();
+
+        let output = build(&fixture, &ctx, project_id, options(), &["app", "tool"]).unwrap();
+        let log = fixture.compiler_log();
+        assert_eq!(log.len(), 4);
+
+        let deps = fixture.root().join("target/release/deps");
+        for (dependent, version) in [("app", "1.0.0"), ("tool", "2.0.0")] {
+            let dup = ctx
+                .packages
+                .iter()
+                .map(|(_, package)| package)
+                .find(|package| package.name == "dup" && package.version == version)
+                .unwrap();
+            let artifact = deps.join(artifact_file_name(
+                &format!("dup-{version}"),
+                Some(LibraryType::Dynamic),
+            ));
+            assert_eq!(output.artifacts(dup.id)[0].path, artifact);
+            assert!(artifact.exists(), "{}", artifact.display());
+
+            let compiled = log
+                .iter()
+                .find(|line| line.starts_with(&format!("--name {dependent} ")))
+                .unwrap();
+            let root = fixture.root().display().to_string();
+            let linked = format!("-d dup={}", artifact.display()).replace(&root, "<root>");
+            assert!(compiled.ends_with(&linked), "{compiled}");
+        }
+
+        build(&fixture, &ctx, project_id, options(), &["app", "tool"]).unwrap();
+        assert_eq!(fixture.compiler_log().len(), 4, "nothing is compiled again");
+        let fingerprints = fixture.root().join("target/release/.fingerprint");
+        assert!(fingerprints.join("dup-1.0.0.toml").exists());
+        assert!(fingerprints.join("dup-2.0.0.toml").exists());
+    }
+
+    #[test]
+    fn report_counts_packages_and_harnesses() {
+        let labels = HashMap::new();
+        let outcome = |failed: Vec<UnitId>| ScheduleOutcome {
+            failed: failed
+                .into_iter()
+                .map(|id| (id, anyhow!("failed")))
+                .collect(),
+            skipped: vec![],
+        };
+        let message = |failed| report(outcome(failed), &labels).unwrap_err().to_string();
+
+        let (ctx, _) = workspace(MEMBERS).load();
+        let a = ctx.get_package_by_name("a").unwrap().id;
+        let b = ctx.get_package_by_name("b").unwrap().id;
+
+        assert!(report(outcome(vec![]), &labels).is_ok());
+        assert_eq!(message(vec![UnitId::Package(a)]), "failed");
+        assert_eq!(
+            message(vec![UnitId::Package(a), UnitId::Package(b)]),
+            "failed to compile 2 packages"
+        );
+        assert_eq!(
+            message(vec![UnitId::Test(0), UnitId::Test(1)]),
+            "failed to compile 2 test harnesses"
+        );
+        assert_eq!(
+            message(vec![UnitId::Package(a), UnitId::Test(0), UnitId::Test(1)]),
+            "failed to compile 1 package and 2 test harnesses"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{Error, Result, anyhow};
+use std::any::Any;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::num::NonZero;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::thread;
+
+/// Runs jobs on up to `jobs` threads, starting a job once the jobs it depends on have succeeded.
+#[derive(Debug, Clone, Copy)]
+pub struct Scheduler {
+    pub jobs: usize,
+    /// Keep running jobs that don't depend on a failed one instead of stopping at the first
+    /// failure.
+    pub keep_going: bool,
+}
+
+#[derive(Debug)]
+pub struct ScheduleOutcome<K> {
+    pub failed: Vec<(K, Error)>,
+    /// Jobs that didn't run because one of their dependencies failed.
+    pub skipped: Vec<K>,
+}
+
+impl<K> ScheduleOutcome<K> {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl Scheduler {
+    pub fn new(jobs: usize, keep_going: bool) -> Self {
+        Self {
+            jobs: jobs.max(1),
+            keep_going,
+        }
+    }
+
+    pub fn default_jobs() -> usize {
+        thread::available_parallelism().map_or(1, NonZero::get)
+    }
+
+    /// `units` are given in dependency order, each with the units it depends on. Dependencies
+    /// that aren't part of `units` are considered done. A job that panics fails its unit.
+    pub fn run<K, T, F>(&self, units: Vec<(K, Vec<K>, T)>, job: F) -> ScheduleOutcome<K>
+    where
+        K: Copy + Eq + Hash + Send,
+        T: Send,
+        F: Fn(T) -> Result<()> + Sync,
+    {
+        let ids: HashSet<_> = units.iter().map(|(id, _, _)| *id).collect();
+        let mut waiting: Vec<_> = units
+            .into_iter()
+            .map(|(id, dependencies, data)| {
+                let dependencies: HashSet<_> = dependencies
+                    .into_iter()
+                    .filter(|dependency| ids.contains(dependency))
+                    .collect();
+                (id, dependencies, data)
+            })
+            .collect();
+
+        let mut outcome = ScheduleOutcome {
+            failed: vec![],
+            skipped: vec![],
+        };
+        let mut unusable = HashSet::new();
+        let mut stopped = false;
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let mut running = 0;
+
+            loop {
+                while !stopped && running < self.jobs {
+                    let Some(index) = waiting.iter().position(|(_, deps, _)| deps.is_empty())
+                    else {
+                        break;
+                    };
+
+                    let (id, _, data) = waiting.remove(index);
+                    let sender = sender.clone();
+                    let job = &job;
+                    scope.spawn(move || {
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| job(data)))
+                            .unwrap_or_else(|payload| Err(panic_error(payload.as_ref())));
+                        let _ = sender.send((id, result));
+                    });
+                    running += 1;
+                }
+
+                if running == 0 {
+                    break;
+                }
+
+                // every job sends its result, even when it panics
+                let (id, result) = receiver.recv().expect("a job didn't report back");
+                running -= 1;
+
+                match result {
+                    Ok(()) => {
+                        for (_, dependencies, _) in &mut waiting {
+                            dependencies.remove(&id);
+                        }
+                    }
+                    Err(error) => {
+                        outcome.failed.push((id, error));
+                        stopped = !self.keep_going;
+                        unusable.insert(id);
+
+                        // dependencies come first, so a single pass reaches every dependent
+                        let mut index = 0;
+                        while index < waiting.len() {
+                            if waiting[index].1.iter().any(|dep| unusable.contains(dep)) {
+                                let (skipped, _, _) = waiting.remove(index);
+                                unusable.insert(skipped);
+                                outcome.skipped.push(skipped);
+                            } else {
+                                index += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        outcome
+    }
+}
+
+fn panic_error(payload: &(dyn Any + Send)) -> Error {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    anyhow!("panicked: {}", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use std::sync::Mutex;
+
+    /// Runs `units` by name, failing the ones in `failing`, and returns the outcome with the
+    /// order the jobs finished in.
+    fn run(
+        scheduler: Scheduler,
+        units: &[(&'static str, &[&'static str])],
+        failing: &[&str],
+    ) -> (ScheduleOutcome<&'static str>, Vec<&'static str>) {
+        let finished = Mutex::new(vec![]);
+        let units = units
+            .iter()
+            .map(|&(id, dependencies)| (id, dependencies.to_vec(), id))
+            .collect();
+        let outcome = scheduler.run(units, |id| {
+            finished.lock().unwrap().push(id);
+            if failing.contains(&id) {
+                bail!("{id} failed");
+            }
+            Ok(())
+        });
+        (outcome, finished.into_inner().unwrap())
+    }
+
+    fn failed<K: Copy>(outcome: &ScheduleOutcome<K>) -> Vec<K> {
+        outcome.failed.iter().map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn dependencies_finish_first() {
+        let units: &[(&str, &[&str])] = &[
+            ("d", &[]),
+            ("c", &["d"]),
+            ("b", &["d"]),
+            ("a", &["b", "c", "external"]),
+        ];
+        let (outcome, finished) = run(Scheduler::new(4, false), units, &[]);
+
+        assert!(outcome.is_success());
+        assert_eq!(finished.len(), 4);
+        assert_eq!(finished[0], "d");
+        assert_eq!(finished[3], "a");
+    }
+
+    #[test]
+    fn failure_stops_scheduling() {
+        let units: &[(&str, &[&str])] = &[("a", &[]), ("b", &[]), ("c", &[])];
+        let (outcome, finished) = run(Scheduler::new(1, false), units, &["a"]);
+
+        assert_eq!(failed(&outcome), ["a"]);
+        assert!(outcome.skipped.is_empty());
+        assert_eq!(finished, ["a"]);
+    }
+
+    #[test]
+    fn keep_going_skips_dependents_of_failures_transitively() {
+        let units: &[(&str, &[&str])] = &[
+            ("a", &[]),
+            ("b", &["a"]),
+            ("c", &["b"]),
+            ("d", &[]),
+            ("e", &["d"]),
+        ];
+        let (outcome, finished) = run(Scheduler::new(1, true), units, &["a"]);
+
+        assert_eq!(failed(&outcome), ["a"]);
+        assert_eq!(outcome.skipped, ["b", "c"]);
+        assert_eq!(finished, ["a", "d", "e"]);
+    }
+
+    #[test]
+    fn panic_fails_the_unit() {
+        let units = vec![("a", vec![], ()), ("b", vec!["a"], ())];
+        let outcome = Scheduler::new(2, true).run(units, |()| -> Result<()> {
+            panic!("boom");
+        });
+
+        assert_eq!(failed(&outcome), ["a"]);
+        assert_eq!(outcome.failed[0].1.to_string(), "panicked: boom");
+        assert_eq!(outcome.skipped, ["b"]);
+    }
+}
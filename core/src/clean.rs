@@ -0,0 +1,108 @@
+use crate::{TargetLayout, package_doc_dir};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Files and directories removed by `kl clean`.
+#[derive(Debug, Default)]
+pub struct CleanPlan {
+    pub paths: Vec<PathBuf>,
+}
+
+impl CleanPlan {
+    /// Without a profile every profile is cleaned and without packages everything the profiles
+    /// contain, so with neither the whole target directory goes.
+    pub fn new(target_dir: &Path, profile: Option<&str>, packages: &[&str]) -> Result<Self> {
+        if profile.is_none() && packages.is_empty() {
+            let paths = target_dir
+                .exists()
+                .then(|| target_dir.to_path_buf())
+                .into_iter()
+                .collect();
+            return Ok(Self { paths });
+        }
+
+        let layouts = TargetLayout::existing(target_dir)?
+            .into_iter()
+            .filter(|layout| profile.is_none_or(|profile| layout.profile() == profile));
+
+        let mut paths = vec![];
+        // documentation isn't built per profile
+        if profile.is_none() {
+            for package in packages {
+                let doc_dir = package_doc_dir(target_dir, package);
+                if doc_dir.exists() {
+                    paths.push(doc_dir);
+                }
+            }
+        }
+
+        for layout in layouts {
+            if packages.is_empty() {
+                paths.push(layout.dest().to_path_buf());
+            } else {
+                for package in packages {
+                    paths.extend(layout.package_files(package));
+                }
+            }
+        }
+
+        Ok(Self { paths })
+    }
+
+    /// Number of files and their total size in bytes.
+    pub fn stats(&self) -> Result<(u64, u64)> {
+        let mut files = 0;
+        let mut size = 0;
+        for path in &self.paths {
+            let (path_files, path_size) = disk_usage(path)?;
+            files += path_files;
+            size += path_size;
+        }
+        Ok((files, size))
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        for path in &self.paths {
+            if path.is_dir() {
+                fs_err::remove_dir_all(path)?;
+            } else if path.exists() {
+                fs_err::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn disk_usage(path: &Path) -> Result<(u64, u64)> {
+    let metadata = fs_err::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok((1, metadata.len()));
+    }
+
+    let mut files = 0;
+    let mut size = 0;
+    for entry in fs_err::read_dir(path)? {
+        let (entry_files, entry_size) = disk_usage(&entry?.path())?;
+        files += entry_files;
+        size += entry_size;
+    }
+    Ok((files, size))
+}
+
+/// Formats a size in bytes with binary units, e.g. `1.5MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes}B")
+    } else {
+        format!("{size:.1}{}", UNITS[unit])
+    }
+}
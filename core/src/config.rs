@@ -0,0 +1,288 @@
+//! Kelpie's own configuration, as opposed to the manifests of packages.
+//!
+//! Values are read from these places, each overriding the ones before it:
+//!
+//! 1. the user configuration, `config.toml` in the kelpie home, which is
+//!    `$XDG_CONFIG_HOME/kelpie/config.toml` unless `KELPIE_HOME` is set
+//! 2. `.kelpie/config.toml` in the current directory and every directory above it, the
+//!    closest one winning
+//! 3. environment variables named `KELPIE_<SECTION>_<KEY>` for the sections in
+//!    [`ENV_SECTIONS`], with underscores in the key standing for dashes, like
+//!    `KELPIE_BUILD_TARGET_DIR` for `build.target-dir`. `KELPIE_TARGET_DIR` sets it too, with
+//!    a lower precedence. Values are read like the ones of `--config`
+//! 4. `--config key=value` on the command line, where the value is TOML and anything that
+//!    isn't is taken as a string, like `--config build.jobs=2` or
+//!    `--config toolchain.compiler=/opt/zirael`
+//!
+//! Tables are merged key by key, other values including arrays are replaced. Relative paths in
+//! a file are relative to the directory holding `.kelpie`, or to the kelpie home for the user
+//! configuration. Elsewhere they are relative to the current directory. A compiler given
+//! without any slash is looked up on `PATH` instead.
+
+use crate::{TARGET_DIR_ENV, TomlAlias, kelpie_home};
+use anyhow::{Result, anyhow, bail};
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Directory of a project holding its kelpie configuration, next to the manifest or above it.
+pub const KELPIE_CONFIG_DIR: &str = ".kelpie";
+pub const KELPIE_CONFIG_FILE: &str = "config.toml";
+
+/// Sections of the configuration that environment variables can set, the other ones are
+/// tables keyed by names that don't fit in a variable.
+pub const ENV_SECTIONS: &[&str] = &["build", "toolchain", "term"];
+
+/// Keys whose relative paths are resolved against the file they are in.
+const PATH_KEYS: &[&str] = &["build.target-dir", "toolchain.compiler"];
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct KelpieConfig {
+    pub build: BuildConfig,
+    pub toolchain: ToolchainConfig,
+    /// Package registries by name.
+    pub registries: BTreeMap<String, RegistryConfig>,
+    /// Command aliases, see [`crate::Aliases`].
+    pub alias: BTreeMap<String, TomlAlias>,
+    pub term: TermConfig,
+    /// Where each alias was last set, the path of a file or `--config`.
+    #[serde(skip)]
+    pub alias_origins: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BuildConfig {
+    /// Number of packages compiled in parallel when `-j` isn't given.
+    pub jobs: Option<usize>,
+    /// Target directory when `--target-dir` isn't given, used instead of the manifest's.
+    pub target_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ToolchainConfig {
+    /// The Zirael compiler to use.
+    pub compiler: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RegistryConfig {
+    pub index: String,
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TermConfig {
+    /// Log debug output as if `-v` was given.
+    pub verbose: Option<bool>,
+    /// Only log warnings and errors.
+    pub quiet: Option<bool>,
+}
+
+impl KelpieConfig {
+    /// Merges everything that configures kelpie when run from `cwd`, with `overrides` from
+    /// `--config`.
+    pub fn load(cwd: &Path, overrides: &[String]) -> Result<Self> {
+        let mut merged = Table::new();
+        let mut alias_origins = BTreeMap::new();
+
+        for (file, base) in Self::files(cwd) {
+            let contents = fs_err::read_to_string(&file)?;
+            let mut table: Table = toml::from_str(&contents)
+                .map_err(|e| anyhow!("invalid kelpie config {}: {}", file.display(), e))?;
+            resolve_paths(&mut table, &base);
+            record_aliases(&mut alias_origins, &table, &file.display().to_string());
+            merge(&mut merged, table);
+        }
+
+        for (var, key, value) in env_settings() {
+            let table =
+                parse_setting(&key, &value, cwd).map_err(|e| anyhow!("invalid {}: {}", var, e))?;
+            merge(&mut merged, table);
+        }
+
+        for setting in overrides {
+            let table = parse_override(setting, cwd)?;
+            record_aliases(&mut alias_origins, &table, "--config");
+            merge(&mut merged, table);
+        }
+
+        let mut config: Self = merged
+            .try_into()
+            .map_err(|e| anyhow!("invalid kelpie config: {}", e))?;
+        config.alias_origins = alias_origins;
+        Ok(config)
+    }
+
+    /// The configuration files that exist, by increasing precedence, each with the directory
+    /// its relative paths start from.
+    pub fn files(cwd: &Path) -> Vec<(PathBuf, PathBuf)> {
+        let mut files = vec![];
+        if let Ok(home) = kelpie_home() {
+            files.push((home.join(KELPIE_CONFIG_FILE), home));
+        }
+
+        let mut project_files: Vec<(PathBuf, PathBuf)> = cwd
+            .ancestors()
+            .map(|dir| {
+                (
+                    dir.join(KELPIE_CONFIG_DIR).join(KELPIE_CONFIG_FILE),
+                    dir.to_path_buf(),
+                )
+            })
+            .collect();
+        project_files.reverse();
+        files.extend(project_files);
+
+        files.retain(|(file, _)| file.is_file());
+        files
+    }
+}
+
+/// `key=value` from `--config`, the key can be dotted.
+fn parse_override(setting: &str, cwd: &Path) -> Result<Table> {
+    let Some((key, value)) = setting.split_once('=') else {
+        bail!("invalid --config `{setting}`, expected KEY=VALUE");
+    };
+    parse_setting(key.trim(), value, cwd)
+        .map_err(|e| anyhow!("invalid --config `{}`: {}", setting, e))
+}
+
+/// `key = value` as a table, taking the value as a string when it isn't TOML.
+fn parse_setting(key: &str, value: &str, cwd: &Path) -> Result<Table> {
+    let mut table: Table = match toml::from_str(&format!("{key} = {value}")) {
+        Ok(table) => table,
+        Err(_) => {
+            let value = Value::String(value.to_string());
+            toml::from_str(&format!("{key} = {value}"))?
+        }
+    };
+    resolve_paths(&mut table, cwd);
+    Ok(table)
+}
+
+/// The variables setting configuration values, by increasing precedence, each with the key it
+/// sets and its value.
+fn env_settings() -> Vec<(String, String, String)> {
+    let mut settings = vec![];
+    if let Ok(value) = env::var(TARGET_DIR_ENV) {
+        settings.push((
+            TARGET_DIR_ENV.to_string(),
+            "build.target-dir".to_string(),
+            value,
+        ));
+    }
+
+    let mut vars: Vec<(String, String)> = env::vars_os()
+        .filter_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)))
+        .collect();
+    vars.sort();
+    for (var, value) in vars {
+        if let Some(key) = env_key(&var) {
+            settings.push((var, key, value));
+        }
+    }
+    settings
+}
+
+/// `build.target-dir` for `KELPIE_BUILD_TARGET_DIR`, `None` for variables outside of
+/// [`ENV_SECTIONS`].
+fn env_key(var: &str) -> Option<String> {
+    let (section, key) = var.strip_prefix("KELPIE_")?.split_once('_')?;
+    let section = section.to_lowercase();
+    if key.is_empty() || !ENV_SECTIONS.contains(&section.as_str()) {
+        return None;
+    }
+    Some(format!(
+        "{}.{}",
+        section,
+        key.to_lowercase().replace('_', "-")
+    ))
+}
+
+fn record_aliases(origins: &mut BTreeMap<String, String>, table: &Table, origin: &str) {
+    if let Some(Value::Table(aliases)) = table.get("alias") {
+        for name in aliases.keys() {
+            origins.insert(name.clone(), origin.to_string());
+        }
+    }
+}
+
+fn merge(into: &mut Table, from: Table) {
+    for (key, value) in from {
+        match (into.get_mut(&key), value) {
+            (Some(Value::Table(into)), Value::Table(from)) => merge(into, from),
+            (_, value) => {
+                into.insert(key, value);
+            }
+        }
+    }
+}
+
+fn resolve_paths(table: &mut Table, base: &Path) {
+    for key in PATH_KEYS {
+        let (section, name) = key.split_once('.').unwrap();
+        if let Some(Value::Table(section)) = table.get_mut(section)
+            && let Some(Value::String(path)) = section.get(name)
+        {
+            let path = resolve_path(key, path, base);
+            section.insert(name.to_string(), path);
+        }
+    }
+}
+
+fn resolve_path(key: &str, path: &str, base: &Path) -> Value {
+    if key == "toolchain.compiler" && !path.contains(['/', '\\']) {
+        return Value::String(path.to_string());
+    }
+    Value::String(base.join(path).to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_keys() {
+        assert_eq!(env_key("KELPIE_BUILD_JOBS").as_deref(), Some("build.jobs"));
+        assert_eq!(
+            env_key("KELPIE_BUILD_TARGET_DIR").as_deref(),
+            Some("build.target-dir")
+        );
+        assert_eq!(
+            env_key("KELPIE_TERM_VERBOSE").as_deref(),
+            Some("term.verbose")
+        );
+        assert_eq!(env_key("KELPIE_HOME"), None);
+        assert_eq!(env_key("KELPIE_TERM_"), None);
+        assert_eq!(env_key("KELPIE_REGISTRIES_MAIN_TOKEN"), None);
+        assert_eq!(env_key("KELPIE_MANIFEST_PATH"), None);
+        assert_eq!(env_key("CARGO_BUILD_JOBS"), None);
+    }
+
+    #[test]
+    fn settings_are_toml_or_strings() {
+        let cwd = Path::new("/work");
+        assert_eq!(
+            parse_setting("build.jobs", "2", cwd).unwrap(),
+            toml::from_str("build.jobs = 2").unwrap()
+        );
+        assert_eq!(
+            parse_setting("term.verbose", "true", cwd).unwrap(),
+            toml::from_str("term.verbose = true").unwrap()
+        );
+        assert_eq!(
+            parse_setting("build.target-dir", "out", cwd).unwrap(),
+            toml::from_str("build.target-dir = '/work/out'").unwrap()
+        );
+        assert_eq!(
+            parse_setting("toolchain.compiler", "zc", cwd).unwrap(),
+            toml::from_str("toolchain.compiler = 'zc'").unwrap()
+        );
+    }
+}
@@ -16,6 +16,8 @@ pub struct KelpieContext {
     pub packages: Arena<Package>,
     pub path_to_project: HashMap<PathBuf, ProjectId>,
     pub name_to_package: HashMap<String, PackageId>,
+    pub(crate) workspace_dependencies: Option<(PathBuf, TomlDependencies)>,
+    manifest_overrides: HashMap<PathBuf, String>,
 }
 
 impl KelpieContext {
@@ -25,9 +27,26 @@ impl KelpieContext {
             packages: Arena::new(),
             path_to_project: HashMap::new(),
             name_to_package: HashMap::new(),
+            workspace_dependencies: None,
+            manifest_overrides: HashMap::new(),
         }
     }
 
+    /// Makes the context load `contents` instead of reading the manifest at `manifest_path`,
+    /// which lets commands check that an edited manifest resolves before writing it.
+    pub fn override_manifest(&mut self, manifest_path: &Path, contents: String) -> Result<()> {
+        let manifest_path = canonicalize_with_strip(manifest_path)?;
+        self.manifest_overrides.insert(manifest_path, contents);
+        Ok(())
+    }
+
+    pub(crate) fn read_manifest(&self, manifest_path: &Path) -> Result<String> {
+        if let Some(contents) = self.manifest_overrides.get(manifest_path) {
+            return Ok(contents.clone());
+        }
+        Ok(fs_err::read_to_string(manifest_path)?)
+    }
+
     pub fn get_project(&self, id: ProjectId) -> Option<&Project> {
         self.projects.get(id)
     }
@@ -0,0 +1,370 @@
+use crate::{
+    BuildOptions, Builder, Compiler, DependencyGraph, KelpieContext, Package, PackageId, ProjectId,
+    ProjectKind, Scheduler, escape_xml, run_with_line_output,
+};
+use anyhow::{Result, anyhow, bail};
+use log::{debug, error, info};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+pub const DOC_DIR: &str = "doc";
+
+#[derive(Debug, Clone, Default)]
+pub struct DocOptions {
+    /// Only document the packages asked for, not their dependencies.
+    pub no_deps: bool,
+    pub target_dir: Option<PathBuf>,
+    pub configured_target_dir: Option<PathBuf>,
+    pub jobs: Option<usize>,
+}
+
+/// Generates HTML documentation into `target/doc/<package>/`, with an index page listing
+/// everything that was documented.
+///
+/// Packages are checked first, the compiler's documentation mode then reads the interfaces of
+/// their dependencies, and items of documented dependencies link to their pages.
+pub struct Documenter<'a> {
+    ctx: &'a KelpieContext,
+    project_id: ProjectId,
+    compiler: Compiler,
+    options: DocOptions,
+}
+
+impl<'a> Documenter<'a> {
+    pub fn new(ctx: &'a KelpieContext, project_id: ProjectId, options: DocOptions) -> Self {
+        Self {
+            ctx,
+            project_id,
+            compiler: Compiler::default(),
+            options,
+        }
+    }
+
+    pub fn compiler(mut self, compiler: Compiler) -> Self {
+        self.compiler = compiler;
+        self
+    }
+
+    /// Documents `targets` and, unless `no_deps` is set, their dependencies. Returns the path of
+    /// the index page.
+    pub fn document(&self, targets: &[PackageId]) -> Result<PathBuf> {
+        let builder = Builder::new(
+            self.ctx,
+            self.project_id,
+            BuildOptions {
+                mode: "debug",
+                lib_types: None,
+                target: None,
+                target_dir: self.options.target_dir.clone(),
+                configured_target_dir: self.options.configured_target_dir.clone(),
+                jobs: self.options.jobs,
+                compiler: None,
+                keep_going: false,
+                args: vec![],
+                check: true,
+                test: false,
+                bench: false,
+                doc_tests: false,
+            },
+        )?
+        .compiler(self.compiler.clone());
+        let interfaces = builder.build(targets)?;
+        let doc_dir = builder.layout().target_dir().join(DOC_DIR);
+
+        let mut graph = DependencyGraph::new();
+        graph.build_from_project(self.ctx, self.project_id)?;
+
+        let mut documented: HashSet<PackageId> = targets.iter().copied().collect();
+        if !self.options.no_deps {
+            for &target in targets {
+                documented.extend(graph.transitive_dependencies(target));
+            }
+        }
+
+        let mut units = vec![];
+        let mut order = vec![];
+        for package_id in graph.get_compilation_order()? {
+            if !documented.contains(&package_id) {
+                continue;
+            }
+            let package = self.package(package_id)?;
+
+            let mut invocation = self
+                .compiler
+                .invocation(&package.name, &package.full_entrypoint_path())
+                .doc()
+                .output(&package_doc_dir(
+                    builder.layout().target_dir(),
+                    &package.name,
+                ));
+            for dependency in graph.transitive_dependencies(package_id) {
+                let dependency_package = self.package(dependency)?;
+                let interface = interfaces
+                    .artifacts(dependency)
+                    .first()
+                    .map(|artifact| artifact.path.clone())
+                    .ok_or_else(|| anyhow!("{} has no interface", dependency_package.name))?;
+
+                invocation = invocation.dependency(&dependency_package.name, &interface);
+                if documented.contains(&dependency) {
+                    invocation = invocation.doc_link(
+                        &dependency_package.name,
+                        &format!("../{}/index.html", dependency_package.name),
+                    );
+                }
+            }
+
+            units.push((package_id, vec![], (package, invocation)));
+            order.push(package);
+        }
+
+        fs_err::create_dir_all(&doc_dir)?;
+        let outcome = self.scheduler().run(units, |(package, invocation)| {
+            info!("Documenting {} v{}", package.name, package.version);
+
+            let mut cmd = invocation.command();
+            debug!("{:?}", cmd);
+            if !run_with_line_output(&mut cmd)?.success() {
+                bail!("failed to document package {}", package.name);
+            }
+            Ok(())
+        });
+
+        let mut failed = outcome.failed;
+        match failed.len() {
+            0 => {}
+            1 => return Err(failed.remove(0).1),
+            count => {
+                for (_, error) in &failed {
+                    error!("{error:?}");
+                }
+                bail!("failed to document {} packages", count)
+            }
+        }
+
+        let index = doc_dir.join("index.html");
+        fs_err::write(&index, self.index(&order)?)?;
+        Ok(index)
+    }
+
+    /// Lists the workspace members first, then the dependencies that were documented.
+    fn index(&self, packages: &[&Package]) -> Result<String> {
+        let project = self
+            .ctx
+            .get_project(self.project_id)
+            .ok_or_else(|| anyhow!("Failed to get project"))?;
+        let members = match &project.kind {
+            ProjectKind::Workspace(workspace) => workspace.members.clone(),
+            ProjectKind::Package(package) => vec![*package],
+        };
+        let mut packages = packages.to_vec();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let (members, dependencies): (Vec<&Package>, Vec<&Package>) = packages
+            .into_iter()
+            .partition(|package| members.contains(&package.id));
+
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Documentation</title>\n<style>\nbody { font-family: sans-serif; max-width: 60em; margin: 2em auto; }\nli { margin-bottom: 1em; }\n.version { color: #777; }\n.keyword { background: #eee; border-radius: 3px; padding: 0 4px; margin-right: 4px; }\n</style>\n</head>\n<body>\n",
+        );
+        for (title, packages) in [("Packages", members), ("Dependencies", dependencies)] {
+            if packages.is_empty() {
+                continue;
+            }
+
+            let _ = writeln!(html, "<h1>{title}</h1>\n<ul>");
+            for package in packages {
+                index_entry(&mut html, package);
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+
+        Ok(html)
+    }
+
+    fn scheduler(&self) -> Scheduler {
+        Scheduler::new(
+            self.options.jobs.unwrap_or_else(Scheduler::default_jobs),
+            false,
+        )
+    }
+
+    fn package(&self, package_id: PackageId) -> Result<&'a Package> {
+        self.ctx
+            .get_package(package_id)
+            .ok_or_else(|| anyhow!("Failed to get package"))
+    }
+}
+
+fn index_entry(html: &mut String, package: &Package) {
+    let name = escape_xml(&package.name);
+    let _ = writeln!(
+        html,
+        "<li>\n<a href=\"{name}/index.html\">{name}</a> <span class=\"version\">{}</span>",
+        escape_xml(&package.version)
+    );
+
+    if let Some(description) = &package.description {
+        let _ = writeln!(html, "<p>{}</p>", escape_xml(description));
+    }
+
+    let keywords = package.keywords.as_deref().unwrap_or_default();
+    if !keywords.is_empty() {
+        html.push_str("<p>");
+        for keyword in keywords {
+            let _ = write!(
+                html,
+                "<span class=\"keyword\">{}</span>",
+                escape_xml(keyword)
+            );
+        }
+        html.push_str("</p>\n");
+    }
+
+    if let Some(repository) = &package.repository {
+        let repository = escape_xml(repository);
+        let _ = writeln!(html, "<p><a href=\"{repository}\">{repository}</a></p>");
+    }
+
+    html.push_str("</li>\n");
+}
+
+/// Where `kl doc` writes the pages of `package`.
+pub fn package_doc_dir(target_dir: &Path, package: &str) -> PathBuf {
+    target_dir.join(DOC_DIR).join(package)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// The members `app` and `ui` and the dependency `archive`, which sorts before them.
+    fn workspace() -> Fixture {
+        Fixture::new(&[
+            (
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["app", "ui"]
+                "#,
+            ),
+            (
+                "app/config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+                type = "bin"
+                description = "Shows <everything>"
+
+                [dependencies]
+                ui = { version = "0.2.0", path = "../ui" }
+                "#,
+            ),
+            ("app/src/main.zr", "fn main() {}"),
+            (
+                "ui/config.toml",
+                r#"
+                [package]
+                name = "ui"
+                version = "0.2.0"
+                keywords = ["gui", "widgets"]
+                repository = "https://example.com/ui?a=1&b=2"
+
+                [dependencies]
+                archive = { version = "1.0.0", path = "../vendor/archive" }
+                "#,
+            ),
+            ("ui/src/lib.zr", ""),
+            (
+                "vendor/archive/config.toml",
+                r#"
+                [package]
+                name = "archive"
+                version = "1.0.0"
+                "#,
+            ),
+            ("vendor/archive/src/lib.zr", ""),
+        ])
+    }
+
+    fn document(fixture: &Fixture, no_deps: bool) -> String {
+        let (ctx, project_id) = fixture.load();
+        let options = DocOptions {
+            no_deps,
+            target_dir: None,
+            configured_target_dir: None,
+            jobs: Some(1),
+        };
+        let app = ctx.get_package_by_name("app").unwrap().id;
+        let index = Documenter::new(&ctx, project_id, options)
+            .compiler(fixture.stub_compiler())
+            .document(&[app])
+            .unwrap();
+
+        let html = fs_err::read_to_string(index).unwrap();
+        html.split_once("<body>\n").unwrap().1.to_string()
+    }
+
+    fn doc_invocation(fixture: &Fixture, package: &str) -> String {
+        fixture
+            .compiler_log()
+            .into_iter()
+            .find(|line| line.contains("--doc") && line.contains(&format!("--name {package} ")))
+            .unwrap()
+    }
+
+    #[test]
+    fn index_lists_members_before_dependencies() {
+        let fixture = workspace();
+        assert_eq!(
+            document(&fixture, false),
+            r#"<h1>Packages</h1>
+<ul>
+<li>
+<a href="app/index.html">app</a> <span class="version">0.1.0</span>
+<p>Shows &lt;everything&gt;</p>
+</li>
+<li>
+<a href="ui/index.html">ui</a> <span class="version">0.2.0</span>
+<p><span class="keyword">gui</span><span class="keyword">widgets</span></p>
+<p><a href="https://example.com/ui?a=1&amp;b=2">https://example.com/ui?a=1&amp;b=2</a></p>
+</li>
+</ul>
+<h1>Dependencies</h1>
+<ul>
+<li>
+<a href="archive/index.html">archive</a> <span class="version">1.0.0</span>
+</li>
+</ul>
+</body>
+</html>
+"#
+        );
+    }
+
+    #[test]
+    fn dependencies_link_to_their_pages() {
+        let fixture = workspace();
+        document(&fixture, false);
+
+        let app = doc_invocation(&fixture, "app");
+        assert!(app.contains("--doc-link ui=../ui/index.html"), "{app}");
+        assert!(
+            app.contains("--doc-link archive=../archive/index.html"),
+            "{app}"
+        );
+    }
+
+    #[test]
+    fn undocumented_dependencies_are_not_linked() {
+        let fixture = workspace();
+        let body = document(&fixture, true);
+
+        assert!(!body.contains("ui/index.html"), "{body}");
+        let app = doc_invocation(&fixture, "app");
+        assert!(!app.contains("--doc-link"), "{app}");
+    }
+}
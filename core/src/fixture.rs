@@ -0,0 +1,106 @@
+//! Projects on disk for unit tests.
+
+use crate::{Compiler, KelpieContext, ProjectId, find_config};
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::{Path, PathBuf};
+use tempdir::TempDir;
+
+/// Name of the stub compiler written by [`Fixture::stub_compiler`], and of its log.
+const STUB_COMPILER: &str = "zc";
+
+pub(crate) struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    /// Writes `files`, relative paths with their content, into a new temporary directory.
+    pub(crate) fn new(files: &[(&str, &str)]) -> Self {
+        let dir = TempDir::new("kelpie-core").unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs_err::create_dir_all(path.parent().unwrap()).unwrap();
+            fs_err::write(path, content).unwrap();
+        }
+        Self { dir }
+    }
+
+    /// A workspace of the libraries `names`, all at version 0.1.0 and without dependencies.
+    pub(crate) fn libraries(names: &[&str]) -> Self {
+        let members: Vec<String> = names.iter().map(|name| format!("\"{name}\"")).collect();
+        let workspace = format!("[workspace]\nmembers = [{}]\n", members.join(", "));
+
+        let mut files = vec![("config.toml".to_string(), workspace)];
+        for name in names {
+            files.push((
+                format!("{name}/config.toml"),
+                format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n"),
+            ));
+            files.push((format!("{name}/src/lib.zr"), String::new()));
+        }
+
+        let files: Vec<(&str, &str)> = files
+            .iter()
+            .map(|(path, content)| (path.as_str(), content.as_str()))
+            .collect();
+        Self::new(&files)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Loads the project at the root of the fixture.
+    pub(crate) fn load(&self) -> (KelpieContext, ProjectId) {
+        let mut ctx = KelpieContext::new();
+        let project_id = find_config(self.path(), &mut ctx).unwrap();
+        (ctx, project_id)
+    }
+
+    /// A compiler that only creates the artifact it is asked for, and logs its arguments so
+    /// they can be read back with [`Self::compiler_log`].
+    #[cfg(unix)]
+    pub(crate) fn stub_compiler(&self) -> Compiler {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let path = self.path().join(STUB_COMPILER);
+        let script = format!(
+            r#"#!/bin/sh
+if [ "$1" = "--version" ]; then echo "zc 1.0"; exit 0; fi
+echo "$*" >> "$0.log"
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --name) name=$2; shift ;;
+        --output) output=$2; shift ;;
+        --lib) lib=$2; shift ;;
+    esac
+    shift
+done
+case "$lib" in
+    dynamic) file="{DLL_PREFIX}$name{DLL_SUFFIX}" ;;
+    static) file="lib$name.a" ;;
+    *) file="$name" ;;
+esac
+mkdir -p "$output" && touch "$output/$file"
+"#
+        );
+        fs_err::write(&path, script).unwrap();
+        fs_err::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        Compiler::new(path)
+    }
+
+    /// Every invocation of the stub compiler so far, with the root of the fixture replaced by
+    /// `<root>`.
+    pub(crate) fn compiler_log(&self) -> Vec<String> {
+        let root = self.root().display().to_string();
+        fs_err::read_to_string(self.path().join(format!("{STUB_COMPILER}.log")))
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.replace(&root, "<root>"))
+            .collect()
+    }
+
+    /// The root as the project sees it, after resolving symlinks.
+    pub(crate) fn root(&self) -> PathBuf {
+        zirael_utils::prelude::canonicalize_with_strip(self.path()).unwrap()
+    }
+}
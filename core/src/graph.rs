@@ -0,0 +1,188 @@
+use crate::{DependencyGraph, DependencyKind, KelpieContext, PackageId, PackageTree};
+use anyhow::{Result, bail};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz, clusters and styles included.
+    Dot,
+    /// Mermaid flowcharts, which GitHub and most markdown viewers render.
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" => Ok(Self::Dot),
+            "mermaid" => Ok(Self::Mermaid),
+            _ => bail!("unknown graph format `{s}`, expected `dot` or `mermaid`"),
+        }
+    }
+}
+
+/// The dependency graph of a project as a diagram. Workspace members are drawn in a cluster of
+/// their own, and dev- and build-dependencies with dotted and dashed edges.
+pub struct PackageGraph<'a> {
+    ctx: &'a KelpieContext,
+    members: Vec<PackageId>,
+    graph: DependencyGraph,
+    duplicates: HashSet<PackageId>,
+}
+
+impl<'a> PackageGraph<'a> {
+    pub fn new(ctx: &'a KelpieContext, tree: &PackageTree<'a>) -> Self {
+        Self {
+            ctx,
+            members: tree.members().to_vec(),
+            graph: DependencyGraph::from_tree(ctx, tree),
+            duplicates: HashSet::new(),
+        }
+    }
+
+    /// Marks the packages present in several versions, see [`PackageTree::duplicates`].
+    pub fn highlight(&mut self, packages: impl IntoIterator<Item = PackageId>) {
+        self.duplicates.extend(packages);
+    }
+
+    /// Only keeps `packages`, what they depend on and what depends on them.
+    pub fn focus(&mut self, packages: &[PackageId]) {
+        self.graph.retain_related(packages);
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.dot(),
+            GraphFormat::Mermaid => self.mermaid(),
+        }
+    }
+
+    fn dot(&self) -> String {
+        let mut out =
+            String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+
+        let nodes = self.nodes();
+        let (members, others) = self.partition();
+        if !members.is_empty() {
+            out.push_str("    subgraph cluster_workspace {\n");
+            out.push_str("        label=\"workspace\";\n        style=dashed;\n");
+            for package_id in members {
+                let _ = writeln!(
+                    out,
+                    "        {};",
+                    self.dot_node(nodes[&package_id], package_id)
+                );
+            }
+            out.push_str("    }\n");
+        }
+        for package_id in others {
+            let _ = writeln!(
+                out,
+                "    {};",
+                self.dot_node(nodes[&package_id], package_id)
+            );
+        }
+
+        for (from, to, kind) in self.graph.edges() {
+            let style = match kind {
+                DependencyKind::Normal => "",
+                DependencyKind::Development => " [style=dotted, label=\"dev\"]",
+                DependencyKind::Build => " [style=dashed, label=\"build\"]",
+            };
+            let _ = writeln!(out, "    n{} -> n{}{};", nodes[&from], nodes[&to], style);
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn dot_node(&self, node: usize, package_id: PackageId) -> String {
+        let label = self
+            .label(package_id)
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        if self.duplicates.contains(&package_id) {
+            format!("n{node} [label=\"{label}\", style=filled, fillcolor=\"#ffcc99\"]")
+        } else {
+            format!("n{node} [label=\"{label}\"]")
+        }
+    }
+
+    fn mermaid(&self) -> String {
+        let mut out = String::from("flowchart LR\n");
+
+        let nodes = self.nodes();
+        let (members, others) = self.partition();
+        if !members.is_empty() {
+            out.push_str("    subgraph workspace\n");
+            for package_id in members {
+                let _ = writeln!(
+                    out,
+                    "        {}",
+                    self.mermaid_node(nodes[&package_id], package_id)
+                );
+            }
+            out.push_str("    end\n");
+        }
+        for package_id in others {
+            let _ = writeln!(
+                out,
+                "    {}",
+                self.mermaid_node(nodes[&package_id], package_id)
+            );
+        }
+
+        for (from, to, kind) in self.graph.edges() {
+            let arrow = match kind {
+                DependencyKind::Normal => "-->",
+                DependencyKind::Development => "-.->|dev|",
+                DependencyKind::Build => "==>|build|",
+            };
+            let _ = writeln!(out, "    n{} {} n{}", nodes[&from], arrow, nodes[&to]);
+        }
+
+        let duplicates: Vec<String> = self
+            .graph
+            .packages()
+            .filter(|package_id| self.duplicates.contains(package_id))
+            .map(|package_id| format!("n{}", nodes[&package_id]))
+            .collect();
+        if !duplicates.is_empty() {
+            out.push_str("    classDef duplicate fill:#ffcc99\n");
+            let _ = writeln!(out, "    class {} duplicate", duplicates.join(","));
+        }
+
+        out
+    }
+
+    fn mermaid_node(&self, node: usize, package_id: PackageId) -> String {
+        let label = self.label(package_id).replace('"', "#quot;");
+        format!("n{node}[\"{label}\"]")
+    }
+
+    fn label(&self, package_id: PackageId) -> String {
+        match self.ctx.get_package(package_id) {
+            Some(package) => format!("{} v{}", package.name, package.version),
+            None => String::from("?"),
+        }
+    }
+
+    /// Node numbers, in the order of the graph.
+    fn nodes(&self) -> HashMap<PackageId, usize> {
+        self.graph
+            .packages()
+            .enumerate()
+            .map(|(index, package_id)| (package_id, index))
+            .collect()
+    }
+
+    /// The workspace members, then the other packages.
+    fn partition(&self) -> (Vec<PackageId>, Vec<PackageId>) {
+        self.graph
+            .packages()
+            .partition(|package_id| self.members.contains(package_id))
+    }
+}
@@ -0,0 +1,64 @@
+/// 64-bit FNV-1a, for hashes that are persisted or end up in paths. Unlike
+/// [`std::hash::DefaultHasher`], the result doesn't change between Rust releases.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher {
+    state: u64,
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// Adds `bytes` prefixed by their length, so consecutive fields can't run into each other.
+    pub fn update(&mut self, bytes: impl AsRef<[u8]>) -> &mut Self {
+        let bytes = bytes.as_ref();
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+        self
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= u64::from(byte);
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_fixed() {
+        assert_eq!(StableHasher::new().finish(), FNV_OFFSET_BASIS);
+        assert_eq!(
+            StableHasher::new().update("kelpie").finish(),
+            0x472e_b8b2_0822_d391
+        );
+    }
+
+    #[test]
+    fn fields_are_separated() {
+        assert_ne!(
+            StableHasher::new().update("ab").update("c").finish(),
+            StableHasher::new().update("a").update("bc").finish()
+        );
+    }
+}
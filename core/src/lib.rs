@@ -1,8 +1,44 @@
+mod alias;
+mod build;
+mod clean;
+mod config;
 mod ctx;
+mod doc;
+#[cfg(test)]
+mod fixture;
+mod graph;
+mod hash;
+mod metadata;
 mod order;
+mod paths;
+mod plugins;
 mod project;
+mod scaffold;
+mod selection;
+mod target;
+mod template;
+mod testing;
+mod tree;
+mod why;
 
+pub use alias::*;
+pub use build::*;
+pub use clean::*;
+pub use config::*;
 pub use ctx::*;
+pub use doc::*;
+pub use graph::*;
+pub use hash::*;
+pub use metadata::*;
 pub use order::*;
+pub use paths::*;
+pub use plugins::*;
 pub use project::*;
+pub use scaffold::*;
+pub use selection::*;
+pub use target::*;
+pub use template::*;
+pub use testing::*;
+pub use tree::*;
+pub use why::*;
 pub use zirael_core;
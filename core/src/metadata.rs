@@ -0,0 +1,303 @@
+//! The output of `kl metadata`, a stable description of a project for other tools.
+//!
+//! Format version 1 is a JSON object with these fields:
+//!
+//! - `version`: the format version, always `1`
+//! - `workspace_root`: absolute path of the workspace, or of the package outside of one
+//! - `target_directory`: absolute path of the target directory
+//! - `workspace_members`: IDs of the workspace members, or of the package itself
+//! - `packages`: every package of the project and its dependencies, sorted by name and
+//!   version, with:
+//!   - `id`: a package ID specification that `-p` accepts, `path+file://<root>#<name>@<version>`
+//!   - `name`, `version`, `author`, `description`, `license`, `repository`, `homepage` and
+//!     `keywords` from the manifest, `null` when missing
+//!   - `type`: `lib` or `bin`
+//!   - `lib_types`: `static` and `dynamic`, empty for binaries
+//!   - `root`: absolute path of the package directory
+//!   - `entrypoint`: path of the entrypoint, relative to `root`
+//!   - `source`: `git+<url>` for git dependencies, with `?tag=`, `?branch=` or `?rev=` when
+//!     one was given, and `null` for local packages
+//!   - `manifest_path`: absolute path of `config.toml`
+//!   - `features`: features enabled on the package by the packages depending on it
+//!   - `targets`: the `lib` or `bin` target, then the `test` and `bench` targets, each with a
+//!     `name`, `kind` and absolute `src_path`
+//!   - `dependencies`: dependencies as declared in the manifest, with the `name` of the
+//!     package, the version requirement `req`, the `kind` (`normal`, `dev` or `build`) and
+//!     the `features` asked for
+//! - `resolve`: the resolved dependency graph, `null` with `--no-deps`, with:
+//!   - `root`: ID of the package outside of a workspace, `null` in one
+//!   - `nodes`: one node per package with its `id` and its `dependencies`, each with the `pkg`
+//!     ID, the `kind` of the edge and the `features` it enables. Dev-dependencies are only
+//!     resolved for workspace members.
+//!
+//! New fields can be added without changing the format version, removing or changing the
+//! meaning of one needs a new version.
+
+use crate::{
+    DependencyKind, KelpieContext, LibraryType, Package, PackageId, PackageTree, ProjectId,
+    ProjectKind, TestTargetKind, discover_bench_targets, discover_test_targets, resolve_target_dir,
+    workspace_root,
+};
+use anyhow::{Result, anyhow, bail};
+use serde_derive::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use zirael_utils::prelude::PackageType;
+
+pub const METADATA_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Metadata {
+    pub version: u32,
+    pub workspace_root: PathBuf,
+    pub target_directory: PathBuf,
+    pub workspace_members: Vec<String>,
+    pub packages: Vec<MetadataPackage>,
+    pub resolve: Option<MetadataResolve>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataPackage {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub keywords: Option<Vec<String>>,
+    /// `lib` or `bin`.
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+    pub lib_types: Vec<LibraryType>,
+    pub root: PathBuf,
+    pub entrypoint: PathBuf,
+    pub source: Option<String>,
+    pub manifest_path: PathBuf,
+    pub features: Vec<String>,
+    pub targets: Vec<MetadataTarget>,
+    pub dependencies: Vec<MetadataDependency>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataTarget {
+    pub name: String,
+    /// `lib`, `bin`, `test` or `bench`.
+    pub kind: &'static str,
+    pub src_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataDependency {
+    pub name: String,
+    pub req: String,
+    pub kind: DependencyKind,
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataResolve {
+    pub root: Option<String>,
+    pub nodes: Vec<MetadataNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataNode {
+    pub id: String,
+    pub dependencies: Vec<MetadataEdge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataEdge {
+    pub pkg: String,
+    pub kind: DependencyKind,
+    pub features: Vec<String>,
+}
+
+impl Metadata {
+    /// Describes the project, and unless `no_deps` is set, every package it depends on.
+    /// `target_dir` and `configured_target_dir` override the target directory, see
+    /// [`resolve_target_dir`].
+    pub fn new(
+        ctx: &KelpieContext,
+        project_id: ProjectId,
+        format_version: u32,
+        no_deps: bool,
+        target_dir: Option<&Path>,
+        configured_target_dir: Option<&Path>,
+    ) -> Result<Self> {
+        if format_version != METADATA_FORMAT_VERSION {
+            bail!(
+                "unsupported metadata format version {}, expected {}",
+                format_version,
+                METADATA_FORMAT_VERSION
+            );
+        }
+
+        let project = ctx
+            .get_project(project_id)
+            .ok_or_else(|| anyhow!("Failed to get project"))?;
+        let tree = PackageTree::new(ctx, project_id, &DependencyKind::ALL)?;
+        let package = |package_id| {
+            ctx.get_package(package_id)
+                .ok_or_else(|| anyhow!("Failed to get package"))
+        };
+
+        let mut package_ids: Vec<PackageId> = if no_deps {
+            tree.members().to_vec()
+        } else {
+            tree.packages().collect()
+        };
+        package_ids.sort_by_key(|&id| ctx.get_package(id).map(|p| (&p.name, &p.version)));
+
+        let mut packages = vec![];
+        for &package_id in &package_ids {
+            let features: BTreeSet<String> = tree
+                .packages()
+                .flat_map(|dependent| tree.dependencies(dependent))
+                .filter(|edge| edge.package == package_id)
+                .flat_map(|edge| edge.features.iter().cloned())
+                .collect();
+
+            let package = package(package_id)?;
+            packages.push(MetadataPackage {
+                id: package.spec(),
+                name: package.name.clone(),
+                version: package.version.clone(),
+                author: package.author.clone(),
+                description: package.description.clone(),
+                license: package.license.clone(),
+                repository: package.repository.clone(),
+                homepage: package.homepage.clone(),
+                keywords: package.keywords.clone(),
+                ty: target_kind(package),
+                lib_types: package.lib_types.clone(),
+                root: package.root_path.clone(),
+                entrypoint: package.entrypoint.clone(),
+                source: package.source.clone(),
+                manifest_path: package.manifest_path(),
+                features: features.into_iter().collect(),
+                targets: targets(package_id, package)?,
+                dependencies: declared_dependencies(ctx, project_id, package_id)?,
+            });
+        }
+
+        let resolve = if no_deps {
+            None
+        } else {
+            let mut nodes = vec![];
+            for &package_id in &package_ids {
+                let mut dependencies = vec![];
+                for edge in tree.dependencies(package_id) {
+                    dependencies.push(MetadataEdge {
+                        pkg: package(edge.package)?.spec(),
+                        kind: edge.kind,
+                        features: edge.features.clone(),
+                    });
+                }
+                dependencies.sort_by(|a, b| (&a.pkg, a.kind as u8).cmp(&(&b.pkg, b.kind as u8)));
+
+                nodes.push(MetadataNode {
+                    id: package(package_id)?.spec(),
+                    dependencies,
+                });
+            }
+
+            let root = match project.kind {
+                ProjectKind::Package(package_id) => Some(package(package_id)?.spec()),
+                ProjectKind::Workspace(_) => None,
+            };
+            Some(MetadataResolve { root, nodes })
+        };
+
+        Ok(Self {
+            version: format_version,
+            workspace_root: workspace_root(project)?,
+            target_directory: resolve_target_dir(project, target_dir, configured_target_dir)?,
+            workspace_members: tree
+                .members()
+                .iter()
+                .map(|&id| Ok(package(id)?.spec()))
+                .collect::<Result<_>>()?,
+            packages,
+            resolve,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+}
+
+fn targets(package_id: PackageId, package: &Package) -> Result<Vec<MetadataTarget>> {
+    let mut targets = vec![MetadataTarget {
+        name: package.name.clone(),
+        kind: target_kind(package),
+        src_path: package.full_entrypoint_path(),
+    }];
+
+    let discovered = discover_test_targets(package_id, package)?
+        .into_iter()
+        .chain(discover_bench_targets(package_id, package)?);
+    for target in discovered {
+        let kind = match target.kind {
+            TestTargetKind::Integration => "test",
+            TestTargetKind::Bench => "bench",
+            // the unit tests and doc tests come from the lib or bin target
+            TestTargetKind::Unit | TestTargetKind::Doc => continue,
+        };
+        targets.push(MetadataTarget {
+            name: target.name,
+            kind,
+            src_path: target.source,
+        });
+    }
+
+    Ok(targets)
+}
+
+fn target_kind(package: &Package) -> &'static str {
+    match package.ty {
+        PackageType::Library => "lib",
+        PackageType::Binary => "bin",
+    }
+}
+
+/// The dependencies in the package's manifest, and for a workspace member the ones in the
+/// workspace manifest that every member depends on.
+fn declared_dependencies(
+    ctx: &KelpieContext,
+    project_id: ProjectId,
+    package_id: PackageId,
+) -> Result<Vec<MetadataDependency>> {
+    let mut declared = vec![];
+    if let Some(project) = ctx.get_project(project_id)
+        && let ProjectKind::Workspace(workspace) = &project.kind
+        && workspace.members.contains(&package_id)
+    {
+        declared.extend(&project.dependencies);
+    }
+    if let Some(project) = ctx
+        .find_project_by_package_id(package_id)
+        .and_then(|project_id| ctx.get_project(project_id))
+    {
+        declared.extend(&project.dependencies);
+    }
+
+    let mut dependencies = vec![];
+    for dep in declared {
+        let package = ctx
+            .get_package(dep.id)
+            .ok_or_else(|| anyhow!("Failed to get package"))?;
+        dependencies.push(MetadataDependency {
+            name: package.name.clone(),
+            req: dep.version.clone(),
+            kind: dep.kind,
+            features: dep.features.clone(),
+        });
+    }
+    dependencies.sort_by(|a, b| (&a.name, a.kind as u8).cmp(&(&b.name, b.kind as u8)));
+
+    Ok(dependencies)
+}
@@ -1,16 +1,21 @@
 use crate::ctx::KelpieContext;
-use crate::{Package, PackageId, Project, ProjectId, ProjectKind};
+use crate::{
+    Dependency, DependencyKind, Package, PackageId, PackageTree, Project, ProjectId, ProjectKind,
+};
 use anyhow::Result;
 use petgraph::{
     Direction,
     algo::toposort,
     graph::{DiGraph, NodeIndex},
+    visit::{Dfs, EdgeRef, Reversed},
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+/// Packages and the kind of each dependency between them. A package can depend on another in
+/// several ways, with an edge per kind.
 #[derive(Debug, Default)]
 pub struct DependencyGraph {
-    graph: DiGraph<PackageId, ()>,
+    graph: DiGraph<PackageId, DependencyKind>,
     package_to_node: HashMap<PackageId, NodeIndex>,
 }
 
@@ -29,34 +34,74 @@ impl DependencyGraph {
             .or_insert_with(|| self.graph.add_node(package_id))
     }
 
-    fn add_dependency(&mut self, dependent: PackageId, dependency: PackageId) {
+    fn add_dependency(
+        &mut self,
+        dependent: PackageId,
+        dependency: PackageId,
+        kind: DependencyKind,
+    ) {
         let dependent_node = self.add_package(dependent);
         let dependency_node = self.add_package(dependency);
 
-        if !self.graph.contains_edge(dependent_node, dependency_node) {
-            self.graph.add_edge(dependent_node, dependency_node, ());
+        let exists = self
+            .graph
+            .edges_connecting(dependent_node, dependency_node)
+            .any(|edge| *edge.weight() == kind);
+        if !exists {
+            self.graph.add_edge(dependent_node, dependency_node, kind);
+        }
+    }
+
+    /// Every package of `tree` and every dependency between them, dev-dependencies included,
+    /// to show the graph rather than build it. Packages are added by name and version and
+    /// dependencies in the same order, so the graph is the same between runs.
+    pub fn from_tree(ctx: &KelpieContext, tree: &PackageTree) -> Self {
+        let mut packages: Vec<&Package> = tree
+            .packages()
+            .filter_map(|package_id| ctx.get_package(package_id))
+            .collect();
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        let mut graph = Self::new();
+        for package in &packages {
+            graph.add_package(package.id);
+        }
+        for package in &packages {
+            let mut edges: Vec<_> = tree
+                .dependencies(package.id)
+                .iter()
+                .map(|edge| (graph.package_to_node[&edge.package], edge.kind))
+                .collect();
+            edges.sort_by_key(|&(node, kind)| (node, kind as u8));
+            for (node, kind) in edges {
+                graph.add_dependency(package.id, graph.graph[node], kind);
+            }
         }
+        graph
     }
 
     pub fn build_from_project(&mut self, ctx: &KelpieContext, project_id: ProjectId) -> Result<()> {
         if let Some(project) = ctx.get_project(project_id) {
             match &project.kind {
                 ProjectKind::Package(package_id) => {
-                    for dep in &project.dependencies {
-                        self.add_dependency(*package_id, dep.id);
-                    }
+                    self.add_package_with_dependencies(ctx, *package_id, &project.dependencies);
                 }
                 ProjectKind::Workspace(workspace) => {
                     for &member_id in &workspace.members {
+                        self.add_package(member_id);
+
                         for dep in &project.dependencies {
-                            self.add_dependency(member_id, dep.id);
+                            self.add_dependency(member_id, dep.id, dep.kind);
+                            self.add_dependency_project(ctx, dep.id);
                         }
 
                         if let Some(member_project_id) = ctx.find_project_by_package_id(member_id) {
                             if let Some(member_project) = ctx.get_project(member_project_id) {
-                                for dep in &member_project.dependencies {
-                                    self.add_dependency(member_id, dep.id);
-                                }
+                                self.add_package_with_dependencies(
+                                    ctx,
+                                    member_id,
+                                    &member_project.dependencies,
+                                );
                             }
                         }
                     }
@@ -66,6 +111,63 @@ impl DependencyGraph {
         Ok(())
     }
 
+    fn add_package_with_dependencies(
+        &mut self,
+        ctx: &KelpieContext,
+        package_id: PackageId,
+        dependencies: &[Dependency],
+    ) {
+        self.add_package(package_id);
+
+        // dev-dependencies are only needed when testing
+        for dep in dependencies {
+            if dep.kind != DependencyKind::Development {
+                self.add_dependency(package_id, dep.id, dep.kind);
+                self.add_dependency_project(ctx, dep.id);
+            }
+        }
+    }
+
+    /// Adds the dev-dependencies of `package_id` and what they depend on, without making the
+    /// package itself depend on them, so they don't end up in its normal build.
+    pub fn add_dev_dependencies(
+        &mut self,
+        ctx: &KelpieContext,
+        package_id: PackageId,
+    ) -> Vec<PackageId> {
+        let Some(project) = ctx
+            .find_project_by_package_id(package_id)
+            .and_then(|project_id| ctx.get_project(project_id))
+        else {
+            return vec![];
+        };
+
+        let mut dev_dependencies = vec![];
+        for dep in &project.dependencies {
+            if dep.kind == DependencyKind::Development {
+                self.add_package(dep.id);
+                self.add_dependency_project(ctx, dep.id);
+                dev_dependencies.push(dep.id);
+            }
+        }
+        dev_dependencies
+    }
+
+    /// Adds the dependencies of a dependency, so the graph covers the whole build.
+    fn add_dependency_project(&mut self, ctx: &KelpieContext, package_id: PackageId) {
+        let node = self.package_to_node[&package_id];
+        if self.graph.neighbors(node).next().is_some() {
+            return;
+        }
+
+        if let Some(project) = ctx
+            .find_project_by_package_id(package_id)
+            .and_then(|project_id| ctx.get_project(project_id))
+        {
+            self.add_package_with_dependencies(ctx, package_id, &project.dependencies);
+        }
+    }
+
     pub fn get_compilation_order(&self) -> Result<Vec<PackageId>> {
         match toposort(&self.graph, None) {
             Ok(mut order) => {
@@ -75,4 +177,81 @@ impl DependencyGraph {
             Err(_) => anyhow::bail!("Circular dependency detected in the project"),
         }
     }
+
+    /// The packages `package_id` depends on directly, whatever the kind of dependency.
+    pub fn dependencies(&self, package_id: PackageId) -> Vec<PackageId> {
+        let Some(&node) = self.package_to_node.get(&package_id) else {
+            return vec![];
+        };
+
+        let mut dependencies = vec![];
+        for node in self.graph.neighbors_directed(node, Direction::Outgoing) {
+            if !dependencies.contains(&self.graph[node]) {
+                dependencies.push(self.graph[node]);
+            }
+        }
+        dependencies
+    }
+
+    /// Every package, in the order they were added.
+    pub fn packages(&self) -> impl Iterator<Item = PackageId> + '_ {
+        self.graph.node_weights().copied()
+    }
+
+    /// Every dependency as `(dependent, dependency, kind)`, in the order they were added.
+    pub fn edges(&self) -> impl Iterator<Item = (PackageId, PackageId, DependencyKind)> + '_ {
+        self.graph.edge_references().map(|edge| {
+            (
+                self.graph[edge.source()],
+                self.graph[edge.target()],
+                *edge.weight(),
+            )
+        })
+    }
+
+    /// Only keeps `packages`, what they depend on and what depends on them, directly or not.
+    pub fn retain_related(&mut self, packages: &[PackageId]) {
+        let mut kept = HashSet::new();
+        for start in packages
+            .iter()
+            .filter_map(|id| self.package_to_node.get(id))
+        {
+            let mut dependencies = Dfs::new(&self.graph, *start);
+            while let Some(node) = dependencies.next(&self.graph) {
+                kept.insert(node);
+            }
+
+            let reversed = Reversed(&self.graph);
+            let mut dependents = Dfs::new(reversed, *start);
+            while let Some(node) = dependents.next(reversed) {
+                kept.insert(node);
+            }
+        }
+
+        self.graph = self.graph.filter_map(
+            |node, &package_id| kept.contains(&node).then_some(package_id),
+            |_, &kind| Some(kind),
+        );
+        self.package_to_node = self
+            .graph
+            .node_indices()
+            .map(|node| (self.graph[node], node))
+            .collect();
+    }
+
+    /// All packages `package_id` depends on, directly or through other dependencies.
+    pub fn transitive_dependencies(&self, package_id: PackageId) -> Vec<PackageId> {
+        let Some(&node) = self.package_to_node.get(&package_id) else {
+            return vec![];
+        };
+
+        let mut dependencies = vec![];
+        let mut dfs = Dfs::new(&self.graph, node);
+        while let Some(next) = dfs.next(&self.graph) {
+            if next != node {
+                dependencies.push(self.graph[next]);
+            }
+        }
+        dependencies
+    }
 }
@@ -0,0 +1,81 @@
+use anyhow::{Result, anyhow};
+use std::env;
+use std::path::{Component, Path, PathBuf};
+
+pub const KELPIE_HOME_ENV: &str = "KELPIE_HOME";
+
+/// Returns the user-level kelpie directory.
+///
+/// Uses `KELPIE_HOME` if set, then `$XDG_CONFIG_HOME/kelpie`, and finally
+/// `~/.config/kelpie` (`%APPDATA%\kelpie` on Windows).
+pub fn kelpie_home() -> Result<PathBuf> {
+    if let Some(home) = env::var_os(KELPIE_HOME_ENV) {
+        return Ok(PathBuf::from(home));
+    }
+
+    if let Some(config_home) = env::var_os("XDG_CONFIG_HOME") {
+        return Ok(PathBuf::from(config_home).join("kelpie"));
+    }
+
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+    };
+
+    base.map(|base| base.join("kelpie"))
+        .ok_or_else(|| anyhow!("couldn't determine the kelpie home directory"))
+}
+
+/// Returns the directory kelpie caches downloads in, like git checkouts.
+///
+/// Uses `cache` in `KELPIE_HOME` if that is set, then `$XDG_CACHE_HOME/kelpie`, and finally
+/// `~/.cache/kelpie` (`%LOCALAPPDATA%\kelpie` on Windows).
+pub fn kelpie_cache_dir() -> Result<PathBuf> {
+    if let Some(home) = env::var_os(KELPIE_HOME_ENV) {
+        return Ok(PathBuf::from(home).join("cache"));
+    }
+
+    if let Some(cache_home) = env::var_os("XDG_CACHE_HOME") {
+        return Ok(PathBuf::from(cache_home).join("kelpie"));
+    }
+
+    let base = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache"))
+    };
+
+    base.map(|base| base.join("kelpie"))
+        .ok_or_else(|| anyhow!("couldn't determine the kelpie cache directory"))
+}
+
+/// Expresses `path` relative to `base`. Both paths are expected to be absolute.
+pub fn relative_path(base: &Path, path: &Path) -> PathBuf {
+    let base_components: Vec<Component<'_>> = base.components().collect();
+    let path_components: Vec<Component<'_>> = path.components().collect();
+
+    let common = base_components
+        .iter()
+        .zip(&path_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut result = PathBuf::new();
+    for _ in common..base_components.len() {
+        result.push("..");
+    }
+    for component in &path_components[common..] {
+        result.push(component);
+    }
+
+    if result.as_os_str().is_empty() {
+        result.push(".");
+    }
+    result
+}
+
+/// Formats a path for a manifest, always using forward slashes.
+pub fn manifest_path_string(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
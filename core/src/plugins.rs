@@ -0,0 +1,105 @@
+use crate::{find_manifest, kelpie_home, manifest_workspace_root};
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+/// External commands are executables named `kl-<command>`.
+pub const PLUGIN_PREFIX: &str = "kl-";
+/// Directory of the kelpie home that is searched for plugins before `PATH`.
+pub const PLUGIN_BIN_DIR: &str = "bin";
+
+/// Path of the `kl` executable running the plugin.
+pub const KELPIE_ENV: &str = "KELPIE";
+/// Manifest of the project in the current directory, when there is one.
+pub const KELPIE_MANIFEST_PATH_ENV: &str = "KELPIE_MANIFEST_PATH";
+/// Root directory of the workspace in the current directory, or of the package outside of one.
+pub const KELPIE_WORKSPACE_ROOT_ENV: &str = "KELPIE_WORKSPACE_ROOT";
+
+/// Where plugins are looked for, in order: the `bin` directory of the kelpie home, then `PATH`.
+pub fn plugin_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Ok(home) = kelpie_home() {
+        dirs.push(home.join(PLUGIN_BIN_DIR));
+    }
+    if let Some(path) = env::var_os("PATH") {
+        dirs.extend(env::split_paths(&path));
+    }
+    dirs
+}
+
+/// The executable of the `kl-<name>` plugin, the first one in [`plugin_dirs`].
+pub fn find_plugin(name: &str) -> Option<PathBuf> {
+    let file_name = format!("{PLUGIN_PREFIX}{name}{}", env::consts::EXE_SUFFIX);
+    plugin_dirs()
+        .into_iter()
+        .map(|dir| dir.join(&file_name))
+        .find(|path| is_executable(path))
+}
+
+/// Every plugin in [`plugin_dirs`] by command name. Plugins earlier in the search order hide
+/// later ones with the same name.
+pub fn list_plugins() -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    for dir in plugin_dirs() {
+        let Ok(entries) = fs_err::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(PLUGIN_PREFIX))
+                .and_then(|name| name.strip_suffix(env::consts::EXE_SUFFIX))
+            else {
+                continue;
+            };
+
+            if !name.is_empty() && is_executable(&path) {
+                plugins.entry(name.to_string()).or_insert(path);
+            }
+        }
+    }
+    plugins
+}
+
+/// Runs a plugin with `args`, from the current directory. The plugin is told where `kl` and
+/// the project are through [`KELPIE_ENV`], [`KELPIE_MANIFEST_PATH_ENV`] and
+/// [`KELPIE_WORKSPACE_ROOT_ENV`], the last two only being set inside of a project. Only the
+/// manifests are read, so dependencies aren't fetched before the plugin runs.
+pub fn run_plugin(plugin: &Path, args: &[OsString]) -> Result<ExitStatus> {
+    let mut cmd = Command::new(plugin);
+    cmd.args(args).env(KELPIE_ENV, env::current_exe()?);
+
+    if let Some(manifest_path) = find_manifest(env::current_dir()?)? {
+        let workspace_root = manifest_workspace_root(&manifest_path).with_context(|| {
+            format!(
+                "failed to find the workspace of {} for the plugin",
+                manifest_path.display()
+            )
+        })?;
+        cmd.env(KELPIE_MANIFEST_PATH_ENV, &manifest_path)
+            .env(KELPIE_WORKSPACE_ROOT_ENV, workspace_root);
+    }
+
+    debug!("{:?}", cmd);
+    cmd.status()
+        .map_err(|e| anyhow!("failed to run {}: {}", plugin.display(), e))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
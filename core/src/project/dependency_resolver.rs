@@ -1,9 +1,10 @@
+use crate::project::git::{GitReference, checkout_git_dependency};
 use crate::{
-    CONFIG_FILE, Dependency, KelpieContext, ProjectKind, TomlDependencies, TomlDependency,
+    CONFIG_FILE, Dependency, DependencyKind, KelpieContext, ProjectKind, TomlDependencies,
+    TomlDependency,
 };
 use anyhow::{anyhow, bail};
-use std::env::current_dir;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use zirael_core::prelude::{canonicalize_with_strip, debug};
 use zirael_utils::prelude::PackageType;
 
@@ -11,6 +12,7 @@ impl KelpieContext {
     pub fn resolve_dependencies(
         &mut self,
         toml_deps: Option<&TomlDependencies>,
+        kind: DependencyKind,
         base_path: PathBuf,
     ) -> anyhow::Result<Vec<Dependency>> {
         let mut dependencies = Vec::new();
@@ -18,61 +20,110 @@ impl KelpieContext {
         if let Some(deps) = toml_deps {
             for (name, dep) in deps {
                 let dep = match dep {
-                    TomlDependency::Version(version) => {
-                        if let Some(&package_id) = self.name_to_package.get(name) {
-                            Some(Dependency {
-                                id: package_id,
-                                version: version.clone(),
-                            })
-                        } else {
-                            None
-                        }
-                    }
-                    TomlDependency::Detailed(dep) => {
-                        let Some(version) = &dep.version else {
-                            bail!("missing version for dependency: {}", name);
+                    TomlDependency::Detailed(detailed) if detailed.workspace == Some(true) => {
+                        let Some((workspace_root, workspace_dep)) = self.workspace_dependency(name)
+                        else {
+                            bail!("dependency {} is not defined in the workspace", name);
                         };
 
-                        // resolving by path
-                        if let Some(path) = &dep.path {
-                            let config_path = base_path.join(path).join(CONFIG_FILE);
-                            let canonicalized =
-                                canonicalize_with_strip(&config_path).map_err(|_| {
-                                    anyhow!("couldn't resolve path dependency: {}", name)
-                                })?;
-                            debug!(
-                                "resolved dependency {} to {}",
-                                name,
-                                canonicalized.display()
-                            );
-                            let project = self.load_from_manifest(config_path, false)?;
-                            let project = self.get_project(project).unwrap();
-                            let ProjectKind::Package(id) = project.kind else {
-                                bail!("can't import package {} from workspace", name);
-                            };
-
-                            let pkg = self.get_package(id).unwrap();
-                            if pkg.ty != PackageType::Library {
-                                bail!("can't import package {} which is a binary", name);
+                        let mut dep =
+                            self.resolve_dependency(name, &workspace_dep, kind, &workspace_root)?;
+                        for feature in detailed.features.iter().flatten() {
+                            if !dep.features.contains(feature) {
+                                dep.features.push(feature.clone());
                             }
-
-                            Some(Dependency {
-                                id,
-                                version: version.clone(),
-                            })
-                        } else {
-                            None
                         }
+                        dep
                     }
+                    dep => self.resolve_dependency(name, dep, kind, &base_path)?,
                 };
 
-                let Some(dep) = dep else {
-                    bail!("couldn't resolve dependency: {}", name);
-                };
                 dependencies.push(dep);
             }
         }
 
         Ok(dependencies)
     }
+
+    fn workspace_dependency(&self, name: &str) -> Option<(PathBuf, TomlDependency)> {
+        let (root, deps) = self.workspace_dependencies.as_ref()?;
+        let dep = deps.get(name)?;
+        Some((root.clone(), dep.clone()))
+    }
+
+    fn resolve_dependency(
+        &mut self,
+        name: &str,
+        dep: &TomlDependency,
+        kind: DependencyKind,
+        base_path: &Path,
+    ) -> anyhow::Result<Dependency> {
+        let dep = match dep {
+            TomlDependency::Version(version) => {
+                if let Some(&package_id) = self.name_to_package.get(name) {
+                    Some(Dependency {
+                        id: package_id,
+                        version: version.clone(),
+                        kind,
+                        features: vec![],
+                    })
+                } else {
+                    None
+                }
+            }
+            TomlDependency::Detailed(dep) => {
+                let Some(version) = &dep.version else {
+                    bail!("missing version for dependency: {}", name);
+                };
+
+                let mut source = None;
+                let package_root = if let Some(path) = &dep.path {
+                    // resolving by path
+                    Some(base_path.join(path))
+                } else if let Some(url) = &dep.git {
+                    // resolving by git
+                    let reference = GitReference::from_dependency(dep)?;
+                    source = Some(format!("git+{}{}", url, reference.query()));
+                    Some(checkout_git_dependency(name, url, reference)?)
+                } else {
+                    None
+                };
+
+                if let Some(package_root) = package_root {
+                    let config_path = package_root.join(CONFIG_FILE);
+                    let canonicalized = canonicalize_with_strip(&config_path)
+                        .map_err(|_| anyhow!("couldn't resolve path dependency: {}", name))?;
+                    debug!(
+                        "resolved dependency {} to {}",
+                        name,
+                        canonicalized.display()
+                    );
+                    let project = self.load_from_manifest(config_path, false)?;
+                    let project = self.get_project(project).unwrap();
+                    let ProjectKind::Package(id) = project.kind else {
+                        bail!("can't import package {} from workspace", name);
+                    };
+
+                    let pkg = self.packages.get_mut(id).unwrap();
+                    if pkg.ty != PackageType::Library {
+                        bail!("can't import package {} which is a binary", name);
+                    }
+                    if source.is_some() {
+                        pkg.source = source;
+                    }
+
+                    Some(Dependency {
+                        id,
+                        version: version.clone(),
+                        kind,
+                        features: dep.features.clone().unwrap_or_default(),
+                    })
+                } else {
+                    None
+                }
+            }
+        };
+
+        dep.ok_or_else(|| anyhow!("couldn't resolve dependency: {}", name))
+    }
 }
@@ -0,0 +1,168 @@
+use crate::{DependencyKind, TomlDependency};
+use anyhow::{Result, bail};
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value, value};
+
+/// Edits a manifest in place while keeping its formatting and comments.
+#[derive(Debug, Clone)]
+pub struct ManifestEditor {
+    path: PathBuf,
+    document: DocumentMut,
+}
+
+impl ManifestEditor {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let document = fs_err::read_to_string(&path)?.parse()?;
+        Ok(Self { path, document })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn contents(&self) -> String {
+        self.document.to_string()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs_err::write(&self.path, self.contents())?;
+        Ok(())
+    }
+
+    pub fn add_dependency(&mut self, kind: DependencyKind, name: &str, dep: &TomlDependency) {
+        let table = table_mut(self.document.as_table_mut(), kind.table_name());
+        table.insert(name, dependency_item(dep));
+    }
+
+    pub fn remove_dependency(&mut self, kind: DependencyKind, name: &str) -> Result<()> {
+        let table_name = kind.table_name();
+        let removed = self
+            .document
+            .get_mut(table_name)
+            .and_then(Item::as_table_like_mut)
+            .and_then(|table| table.remove(name));
+
+        if removed.is_none() {
+            bail!("dependency {} not found in [{}]", name, table_name);
+        }
+
+        if self
+            .document
+            .get(table_name)
+            .and_then(Item::as_table_like)
+            .is_some_and(|table| table.is_empty())
+        {
+            self.document.remove(table_name);
+        }
+        Ok(())
+    }
+
+    pub fn add_workspace_dependency(&mut self, name: &str, dep: &TomlDependency) {
+        let workspace = table_mut(self.document.as_table_mut(), "workspace");
+        table_mut(workspace, "dependencies").insert(name, dependency_item(dep));
+    }
+
+    pub fn remove_workspace_dependency(&mut self, name: &str) -> Result<()> {
+        let removed = self
+            .document
+            .get_mut("workspace")
+            .and_then(|workspace| workspace.get_mut("dependencies"))
+            .and_then(Item::as_table_like_mut)
+            .and_then(|table| table.remove(name));
+
+        if removed.is_none() {
+            bail!("dependency {} not found in [workspace.dependencies]", name);
+        }
+
+        if let Some(workspace) = self
+            .document
+            .get_mut("workspace")
+            .and_then(Item::as_table_like_mut)
+            && workspace
+                .get("dependencies")
+                .and_then(Item::as_table_like)
+                .is_some_and(|table| table.is_empty())
+        {
+            workspace.remove("dependencies");
+        }
+        Ok(())
+    }
+
+    pub fn workspace_members(&self) -> Vec<String> {
+        self.document
+            .get("workspace")
+            .and_then(|workspace| workspace.get("members"))
+            .and_then(Item::as_array)
+            .map(|members| {
+                members
+                    .iter()
+                    .filter_map(|member| member.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn add_workspace_member(&mut self, member: &str) {
+        let workspace = table_mut(self.document.as_table_mut(), "workspace");
+        let members = workspace
+            .entry("members")
+            .or_insert_with(|| value(Array::new()));
+
+        if let Some(members) = members.as_array_mut() {
+            members.push(member);
+        } else {
+            let mut array = Array::new();
+            array.push(member);
+            *members = value(array);
+        }
+    }
+}
+
+fn table_mut<'a>(parent: &'a mut Table, name: &str) -> &'a mut Table {
+    let item = parent.entry(name).or_insert_with(|| {
+        let mut table = Table::new();
+        table.set_implicit(true);
+        Item::Table(table)
+    });
+
+    if !item.is_table() {
+        *item = Item::Table(Table::new());
+    }
+    item.as_table_mut().unwrap()
+}
+
+fn dependency_item(dep: &TomlDependency) -> Item {
+    match dep {
+        TomlDependency::Version(version) => value(version),
+        TomlDependency::Detailed(dep) => {
+            let mut table = InlineTable::new();
+            if let Some(version) = &dep.version {
+                table.insert("version", version.into());
+            }
+            if let Some(path) = &dep.path {
+                table.insert("path", path.into());
+            }
+            if let Some(git) = &dep.git {
+                table.insert("git", git.into());
+            }
+            if let Some(tag) = &dep.tag {
+                table.insert("tag", tag.into());
+            }
+            if let Some(branch) = &dep.branch {
+                table.insert("branch", branch.into());
+            }
+            if let Some(rev) = &dep.rev {
+                table.insert("rev", rev.into());
+            }
+            if let Some(features) = &dep.features {
+                let features: Array = features.iter().collect();
+                table.insert("features", Value::Array(features));
+            }
+            if let Some(workspace) = dep.workspace {
+                table.insert("workspace", workspace.into());
+            }
+            value(table)
+        }
+    }
+}
@@ -1,28 +1,48 @@
 use crate::ctx::{KelpieContext, ProjectId};
 use crate::project::members::find_workspace_members;
 use crate::project::{CONFIG_FILE, TomlConfig};
-use crate::{Package, PackageBuilder, Project, ProjectKind, Workspace};
+use crate::{DependencyKind, Package, PackageBuilder, Project, ProjectKind, Workspace};
 use anyhow::{Result, bail};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use zirael_core::prelude::canonicalize_with_strip;
+use zirael_utils::prelude::PackageType;
 
 pub fn find_config<P: AsRef<Path>>(start_path: P, ctx: &mut KelpieContext) -> Result<ProjectId> {
-    let mut current = canonicalize_with_strip(start_path.as_ref())?;
+    match find_manifest(start_path)? {
+        Some(manifest_path) => ctx.load_from_manifest(manifest_path, false),
+        None => bail!(
+            "No {} found in current directory or any parent directory",
+            CONFIG_FILE
+        ),
+    }
+}
 
-    loop {
-        let manifest_path = current.join(CONFIG_FILE);
-        if manifest_path.exists() {
-            return ctx.load_from_manifest(manifest_path, false);
+/// The first manifest in `start_path` or its parents, without loading it.
+pub fn find_manifest<P: AsRef<Path>>(start_path: P) -> Result<Option<PathBuf>> {
+    let start_path = canonicalize_with_strip(start_path.as_ref())?;
+    Ok(start_path
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|manifest_path| manifest_path.exists()))
+}
+
+/// Looks for a workspace manifest in the parents of `root_path`, stopping at the first package.
+pub fn find_enclosing_workspace(root_path: &Path) -> Result<Option<PathBuf>> {
+    for dir in root_path.ancestors().skip(1) {
+        let manifest_path = dir.join(CONFIG_FILE);
+        if !manifest_path.exists() {
+            continue;
         }
 
-        match current.parent() {
-            Some(parent) => current = parent.to_path_buf(),
-            None => bail!(
-                "No {} found in current directory or any parent directory",
-                CONFIG_FILE
-            ),
+        let toml = TomlConfig::from_path(&manifest_path)?;
+        if toml.workspace.is_some() {
+            return Ok(Some(manifest_path));
+        }
+        if toml.package.is_some() {
+            return Ok(None);
         }
     }
+    Ok(None)
 }
 
 impl KelpieContext {
@@ -37,7 +57,7 @@ impl KelpieContext {
             return Ok(existing_id);
         }
 
-        let file = fs_err::read_to_string(&manifest_path)?;
+        let file = self.read_manifest(&manifest_path)?;
         let toml: TomlConfig = toml::from_str(&file)?;
 
         if is_workspace_member && toml.workspace.is_some() {
@@ -60,9 +80,15 @@ impl KelpieContext {
             );
         }
 
+        let root_path = manifest_path.parent().unwrap().to_path_buf();
+
         let project_id = if let Some(workspace_config) = toml.workspace {
+            if let Some(dependencies) = &workspace_config.dependencies {
+                self.workspace_dependencies = Some((root_path.clone(), dependencies.clone()));
+            }
+
             let member_package_ids = if let Some(members) = workspace_config.members {
-                let member_paths = find_workspace_members(members)?;
+                let member_paths = find_workspace_members(&root_path, members)?;
 
                 let mut member_ids = Vec::new();
 
@@ -93,20 +119,31 @@ impl KelpieContext {
 
             let dependencies = self.resolve_dependencies(
                 workspace_config.dependencies.as_ref(),
-                manifest_path.parent().unwrap().to_path_buf(),
+                DependencyKind::Normal,
+                root_path,
             )?;
             let workspace = Workspace {
                 members: member_package_ids,
             };
 
-            let workspace_project = Project::new(ProjectKind::Workspace(workspace), dependencies);
+            let workspace_project = Project::new(
+                ProjectKind::Workspace(workspace),
+                dependencies,
+                manifest_path.clone(),
+            );
 
             self.add_project(workspace_project, manifest_path)
         } else if let Some(package_config) = toml.package {
-            let package_builder = PackageBuilder::from_toml(
-                package_config,
-                manifest_path.parent().unwrap().to_path_buf(),
-            );
+            if package_config.r#type == Some(PackageType::Binary)
+                && package_config.lib_type.is_some()
+            {
+                bail!(
+                    "package {} is a binary and cannot specify a lib-type",
+                    package_config.name
+                );
+            }
+
+            let package_builder = PackageBuilder::from_toml(package_config, root_path.clone());
 
             if !package_builder.full_entrypoint_path().exists() {
                 bail!(
@@ -117,12 +154,24 @@ impl KelpieContext {
 
             let package_id = self.add_package(package_builder);
 
-            let dependencies = self.resolve_dependencies(
-                toml.dependencies.as_ref(),
-                manifest_path.parent().unwrap().to_path_buf(),
-            )?;
+            let mut dependencies = Vec::new();
+            for (kind, deps) in [
+                (DependencyKind::Normal, &toml.dependencies),
+                (DependencyKind::Development, &toml.dev_dependencies),
+                (DependencyKind::Build, &toml.build_dependencies),
+            ] {
+                dependencies.extend(self.resolve_dependencies(
+                    deps.as_ref(),
+                    kind,
+                    root_path.clone(),
+                )?);
+            }
 
-            let package_project = Project::new(ProjectKind::Package(package_id), dependencies);
+            let package_project = Project::new(
+                ProjectKind::Package(package_id),
+                dependencies,
+                manifest_path.clone(),
+            );
 
             self.add_project(package_project, manifest_path)
         } else {
@@ -0,0 +1,103 @@
+use crate::{DetailedDependency, StableHasher, kelpie_cache_dir};
+use anyhow::{Result, bail};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zirael_core::prelude::debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitReference<'a> {
+    Tag(&'a str),
+    Branch(&'a str),
+    Rev(&'a str),
+    DefaultBranch,
+}
+
+impl<'a> GitReference<'a> {
+    pub fn from_dependency(dep: &'a DetailedDependency) -> Result<Self> {
+        match (&dep.tag, &dep.branch, &dep.rev) {
+            (Some(tag), None, None) => Ok(Self::Tag(tag)),
+            (None, Some(branch), None) => Ok(Self::Branch(branch)),
+            (None, None, Some(rev)) => Ok(Self::Rev(rev)),
+            (None, None, None) => Ok(Self::DefaultBranch),
+            _ => bail!("only one of `tag`, `branch` or `rev` can be specified"),
+        }
+    }
+
+    /// The reference as a query of a `git+<url>` source.
+    pub fn query(self) -> String {
+        match self {
+            Self::Tag(name) => format!("?tag={name}"),
+            Self::Branch(name) => format!("?branch={name}"),
+            Self::Rev(name) => format!("?rev={name}"),
+            Self::DefaultBranch => String::new(),
+        }
+    }
+
+    fn label(self) -> &'a str {
+        match self {
+            Self::Tag(name) | Self::Branch(name) | Self::Rev(name) => name,
+            Self::DefaultBranch => "HEAD",
+        }
+    }
+}
+
+/// Clones `url` into the git directory of the kelpie cache and returns the checkout directory.
+/// Existing checkouts are reused.
+pub fn checkout_git_dependency(
+    name: &str,
+    url: &str,
+    reference: GitReference<'_>,
+) -> Result<PathBuf> {
+    let url_hash = StableHasher::new().update(url).finish();
+    let checkout = kelpie_cache_dir()?
+        .join("git")
+        .join(format!("{}-{:016x}", name, url_hash))
+        .join(reference.label().replace(['/', '\\'], "-"));
+
+    if checkout.exists() {
+        debug!("reusing git checkout {}", checkout.display());
+        return Ok(checkout);
+    }
+
+    let result = clone_repository(url, &checkout, reference);
+    if result.is_err() {
+        let _ = fs_err::remove_dir_all(&checkout);
+    }
+    result?;
+
+    Ok(checkout)
+}
+
+pub fn clone_repository(url: &str, checkout: &Path, reference: GitReference<'_>) -> Result<()> {
+    let mut cmd = Command::new("git");
+    // a tag is checked out as a detached HEAD, which git would explain on every clone
+    cmd.args(["-c", "advice.detachedHead=false", "clone", "--quiet"]);
+    match reference {
+        GitReference::Tag(name) | GitReference::Branch(name) => {
+            cmd.args(["--depth", "1", "--branch", name]);
+        }
+        GitReference::DefaultBranch => {
+            cmd.args(["--depth", "1"]);
+        }
+        GitReference::Rev(_) => {}
+    }
+    cmd.arg(url).arg(checkout);
+
+    debug!("{:?}", cmd);
+    if !cmd.status()?.success() {
+        bail!("failed to clone git repository {}", url);
+    }
+
+    if let GitReference::Rev(rev) = reference {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(checkout)
+            .args(["checkout", "--quiet", rev])
+            .status()?;
+        if !status.success() {
+            bail!("failed to checkout revision {} of {}", rev, url);
+        }
+    }
+
+    Ok(())
+}
@@ -1,10 +1,9 @@
 use anyhow::Result;
 use anyhow::bail;
-use std::env::current_dir;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-pub fn find_workspace_members(members: Vec<String>) -> Result<Vec<PathBuf>> {
-    let dir = current_dir()?;
+/// Directories of the workspace `members`, relative to the workspace root `dir`.
+pub fn find_workspace_members(dir: &Path, members: Vec<String>) -> Result<Vec<PathBuf>> {
     let mut paths = vec![];
 
     for member in members {
@@ -44,3 +43,20 @@ pub fn find_workspace_members(members: Vec<String>) -> Result<Vec<PathBuf>> {
 
     Ok(paths)
 }
+
+/// Checks whether `path`, relative to the workspace root, is covered by one of the `members`
+/// entries.
+pub fn is_workspace_member(members: &[String], path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+
+    members.iter().any(|member| {
+        let member = member.strip_prefix("./").unwrap_or(member);
+        let member = member.trim_end_matches('/');
+
+        if member.contains('*') {
+            glob::Pattern::new(member).is_ok_and(|pattern| pattern.matches(&path))
+        } else {
+            member == path
+        }
+    })
+}
@@ -1,14 +1,19 @@
 mod dependency_resolver;
+mod editor;
 mod finder;
+mod git;
 mod members;
 mod pretty;
 
+pub use editor::*;
 pub use finder::*;
+pub use git::*;
 use id_arena::Id;
+pub use members::is_workspace_member;
 pub use pretty::*;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use zirael_utils::prelude::PackageType;
 
 pub const CONFIG_FILE: &str = "config.toml";
@@ -18,6 +23,34 @@ pub struct TomlConfig {
     pub workspace: Option<TomlWorkspace>,
     pub package: Option<TomlPackage>,
     pub dependencies: Option<TomlDependencies>,
+    #[serde(rename = "dev-dependencies")]
+    pub dev_dependencies: Option<TomlDependencies>,
+    #[serde(rename = "build-dependencies")]
+    pub build_dependencies: Option<TomlDependencies>,
+    pub build: Option<TomlBuild>,
+    pub profile: Option<HashMap<String, TomlProfile>>,
+}
+
+/// The `[build]` table, only read from the root manifest of a workspace or package.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TomlBuild {
+    #[serde(rename = "target-dir")]
+    pub target_dir: Option<PathBuf>,
+    /// Compiler flags for every package, replaced by `KELPIE_ZFLAGS` when it's set.
+    pub zflags: Option<Vec<String>>,
+}
+
+/// A `[profile.<name>]` table, only read from the root manifest like `[build]`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TomlProfile {
+    pub flags: Option<Vec<String>>,
+}
+
+impl TomlConfig {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let contents = fs_err::read_to_string(path.as_ref())?;
+        Ok(toml::from_str(&contents)?)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -38,32 +71,87 @@ pub struct TomlPackage {
     pub keywords: Option<Vec<String>>,
     pub r#type: Option<PackageType>,
     pub entrypoint: Option<PathBuf>,
+    #[serde(rename = "lib-type")]
+    pub lib_type: Option<Vec<LibraryType>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum LibraryType {
+    Static,
+    Dynamic,
+}
+
+impl LibraryType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Static => "static",
+            Self::Dynamic => "dynamic",
+        }
+    }
 }
 
 pub type TomlDependencies = HashMap<String, TomlDependency>;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum TomlDependency {
     Version(String),
     Detailed(DetailedDependency),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct DetailedDependency {
     pub version: Option<String>,
     pub path: Option<String>,
+    pub git: Option<String>,
+    pub tag: Option<String>,
+    pub branch: Option<String>,
+    pub rev: Option<String>,
+    pub features: Option<Vec<String>>,
+    pub workspace: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum DependencyKind {
+    #[serde(rename = "normal")]
+    Normal,
+    #[serde(rename = "dev")]
+    Development,
+    #[serde(rename = "build")]
+    Build,
+}
+
+impl DependencyKind {
+    pub const ALL: [Self; 3] = [Self::Normal, Self::Development, Self::Build];
+
+    pub fn table_name(self) -> &'static str {
+        match self {
+            Self::Normal => "dependencies",
+            Self::Development => "dev-dependencies",
+            Self::Build => "build-dependencies",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Project {
     pub kind: ProjectKind,
     pub dependencies: Vec<Dependency>,
+    pub manifest_path: PathBuf,
 }
 
 impl Project {
-    pub fn new(kind: ProjectKind, dependencies: Vec<Dependency>) -> Self {
-        Self { kind, dependencies }
+    pub fn new(kind: ProjectKind, dependencies: Vec<Dependency>, manifest_path: PathBuf) -> Self {
+        Self {
+            kind,
+            dependencies,
+            manifest_path,
+        }
+    }
+
+    pub fn root_path(&self) -> &Path {
+        self.manifest_path.parent().unwrap()
     }
 }
 
@@ -82,6 +170,8 @@ pub struct Workspace {
 pub struct Dependency {
     pub id: PackageId,
     pub version: String,
+    pub kind: DependencyKind,
+    pub features: Vec<String>,
 }
 
 pub type PackageId = Id<Package>;
@@ -98,8 +188,12 @@ pub struct Package {
     pub homepage: Option<String>,
     pub keywords: Option<Vec<String>>,
     pub ty: PackageType,
+    /// Kinds of library to build, empty for binaries.
+    pub lib_types: Vec<LibraryType>,
     pub root_path: PathBuf,
     pub entrypoint: PathBuf,
+    /// `git+<url>` for packages checked out from git, `None` for local ones.
+    pub source: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -113,12 +207,22 @@ pub struct PackageBuilder {
     pub homepage: Option<String>,
     pub keywords: Option<Vec<String>>,
     pub ty: PackageType,
+    pub lib_types: Vec<LibraryType>,
     pub root_path: PathBuf,
     pub entrypoint: PathBuf,
 }
 
 impl PackageBuilder {
     pub fn from_toml(toml_package: TomlPackage, root_path: PathBuf) -> Self {
+        let ty = toml_package.r#type.clone().unwrap_or(PackageType::Library);
+        let lib_types = match ty {
+            PackageType::Library => toml_package
+                .lib_type
+                .filter(|types| !types.is_empty())
+                .unwrap_or_else(|| vec![LibraryType::Dynamic]),
+            PackageType::Binary => vec![],
+        };
+
         Self {
             name: toml_package.name,
             version: toml_package.version,
@@ -128,7 +232,8 @@ impl PackageBuilder {
             repository: toml_package.repository,
             homepage: toml_package.homepage,
             keywords: toml_package.keywords,
-            ty: toml_package.r#type.clone().unwrap_or(PackageType::Library),
+            ty,
+            lib_types,
             root_path: root_path.clone(),
             entrypoint: toml_package.entrypoint.unwrap_or_else(|| {
                 match toml_package.r#type.unwrap_or(PackageType::Library) {
@@ -151,8 +256,10 @@ impl PackageBuilder {
             homepage: self.homepage,
             keywords: self.keywords,
             ty: self.ty,
+            lib_types: self.lib_types,
             root_path: self.root_path,
             entrypoint: self.entrypoint,
+            source: None,
         }
     }
 
@@ -165,4 +272,18 @@ impl Package {
     pub fn full_entrypoint_path(&self) -> PathBuf {
         self.root_path.join(&self.entrypoint)
     }
+
+    /// A package ID specification matching only this package, see [`crate::PackageSpec`].
+    pub fn spec(&self) -> String {
+        format!(
+            "path+file://{}#{}@{}",
+            self.root_path.display(),
+            self.name,
+            self.version
+        )
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root_path.join(CONFIG_FILE)
+    }
 }
@@ -1,10 +1,10 @@
 use crate::{KelpieContext, ProjectId, ProjectKind};
 use std::io::{self, Write};
 
-const TREE_BRANCH: &str = "├── ";
-const TREE_CORNER: &str = "└── ";
-const TREE_VERTICAL: &str = "│   ";
-const TREE_SPACE: &str = "    ";
+pub(crate) const TREE_BRANCH: &str = "├── ";
+pub(crate) const TREE_CORNER: &str = "└── ";
+pub(crate) const TREE_VERTICAL: &str = "│   ";
+pub(crate) const TREE_SPACE: &str = "    ";
 
 pub fn print_project_tree(ctx: &KelpieContext, project_id: ProjectId, indent: usize) -> io::Result<()> {
     let mut stdout = io::stdout().lock();
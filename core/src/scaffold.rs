@@ -0,0 +1,186 @@
+use crate::{
+    CONFIG_FILE, ManifestEditor, PackageBuilder, Template, TomlConfig, builtin_template_values,
+    find_enclosing_workspace, is_workspace_member, manifest_path_string, relative_path,
+};
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use zirael_core::prelude::canonicalize_with_strip;
+use zirael_utils::prelude::PackageType;
+
+const GITIGNORE: &str = "/target\n";
+const LIB_SOURCE: &str = "fn add(a: int, b: int): int {\n    a + b\n}\n";
+const MAIN_SOURCE: &str = "fn main() {}\n";
+
+#[derive(Debug)]
+pub struct NewPackage {
+    pub path: PathBuf,
+    pub name: Option<String>,
+    pub ty: PackageType,
+    pub template: Option<Template>,
+    /// Placeholder values for the template, in addition to the builtin ones.
+    pub values: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatedPackage {
+    pub name: String,
+    pub ty: PackageType,
+    pub root_path: PathBuf,
+    /// Manifest of the workspace the package was added to as a member.
+    pub workspace_manifest: Option<PathBuf>,
+}
+
+impl NewPackage {
+    pub fn new(path: PathBuf, ty: PackageType) -> Self {
+        Self {
+            path,
+            name: None,
+            ty,
+            template: None,
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn name(mut self, name: Option<String>) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn template(mut self, template: Option<Template>) -> Self {
+        self.template = template;
+        self
+    }
+
+    pub fn values(mut self, values: HashMap<String, String>) -> Self {
+        self.values = values;
+        self
+    }
+
+    /// The name the package will be created with.
+    pub fn package_name(&self) -> Result<String> {
+        match canonicalize_with_strip(&self.path) {
+            Ok(root_path) => self.name_for(&root_path),
+            Err(_) => self.name_for(&self.path),
+        }
+    }
+
+    /// Creates the package in a new directory, failing if it already exists.
+    pub fn create(&self) -> Result<CreatedPackage> {
+        if self.path.exists() {
+            bail!("destination {} already exists", self.path.display());
+        }
+        self.name_for(&self.path)?;
+        fs_err::create_dir_all(&self.path)?;
+        self.init()
+    }
+
+    /// Creates the package in an existing directory, keeping files that are already there.
+    pub fn init(&self) -> Result<CreatedPackage> {
+        let root_path = canonicalize_with_strip(&self.path)?;
+        let manifest_path = root_path.join(CONFIG_FILE);
+        if manifest_path.exists() {
+            bail!("{} already exists in {}", CONFIG_FILE, self.path.display());
+        }
+
+        let name = self.name_for(&root_path)?;
+        let workspace_manifest = find_enclosing_workspace(&root_path)?;
+
+        if let Some(template) = &self.template {
+            let mut values = builtin_template_values(&name);
+            values.extend(self.values.clone());
+            template.render(&root_path, &values)?;
+        }
+
+        // templates may provide their own manifest, which then decides the entrypoint
+        write_if_missing(&manifest_path, &self.manifest(&name))?;
+        let mut ty = self.ty.clone();
+        if let Some(package) = TomlConfig::from_path(&manifest_path)?.package {
+            let package = PackageBuilder::from_toml(package, root_path.clone());
+            let source = match package.ty {
+                PackageType::Library => LIB_SOURCE,
+                PackageType::Binary => MAIN_SOURCE,
+            };
+            write_if_missing(&package.full_entrypoint_path(), source)?;
+            ty = package.ty;
+        }
+        write_if_missing(&root_path.join(".gitignore"), GITIGNORE)?;
+
+        let workspace_manifest = match workspace_manifest {
+            Some(workspace_manifest) => {
+                add_workspace_member(&workspace_manifest, &root_path)?.then_some(workspace_manifest)
+            }
+            None => None,
+        };
+
+        Ok(CreatedPackage {
+            name,
+            ty,
+            root_path,
+            workspace_manifest,
+        })
+    }
+
+    fn name_for(&self, root_path: &Path) -> Result<String> {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => root_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_string)
+                .unwrap_or_default(),
+        };
+        validate_package_name(&name)?;
+        Ok(name)
+    }
+
+    fn manifest(&self, name: &str) -> String {
+        let mut manifest = format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n");
+        if self.ty == PackageType::Binary {
+            manifest.push_str("type = \"bin\"\n");
+        }
+        manifest.push_str("\n[dependencies]\n");
+        manifest
+    }
+}
+
+pub fn validate_package_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("package name cannot be empty");
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        bail!("package name `{}` cannot start with a digit", name);
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+    {
+        bail!("invalid character `{}` in package name `{}`", c, name);
+    }
+    Ok(())
+}
+
+fn write_if_missing(path: &Path, contents: &str) -> Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs_err::create_dir_all(parent)?;
+    }
+    fs_err::write(path, contents)?;
+    Ok(())
+}
+
+/// Appends the package to the workspace members, returns `false` if it was already covered.
+fn add_workspace_member(workspace_manifest: &Path, root_path: &Path) -> Result<bool> {
+    let mut editor = ManifestEditor::open(workspace_manifest)?;
+    let relative = relative_path(workspace_manifest.parent().unwrap(), root_path);
+
+    if is_workspace_member(&editor.workspace_members(), &relative) {
+        return Ok(false);
+    }
+
+    editor.add_workspace_member(&manifest_path_string(&relative));
+    editor.save()?;
+    Ok(true)
+}
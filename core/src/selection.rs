@@ -0,0 +1,164 @@
+use crate::{KelpieContext, Package, PackageId, ProjectId, ProjectKind};
+use anyhow::{Result, anyhow, bail};
+use std::path::PathBuf;
+use zirael_core::prelude::canonicalize_with_strip;
+
+/// A package given on the command line: a name or glob pattern, `name@version`, or
+/// `path+file:///path/to/package#name@version` where the fragment is optional.
+#[derive(Debug, Clone)]
+pub enum PackageSpec {
+    Name {
+        pattern: glob::Pattern,
+        version: Option<String>,
+    },
+    Path {
+        path: PathBuf,
+        name: Option<String>,
+        version: Option<String>,
+    },
+}
+
+impl PackageSpec {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let url = spec.strip_prefix("path+").unwrap_or(spec);
+        if let Some(rest) = url.strip_prefix("file://") {
+            let (path, fragment) = match rest.split_once('#') {
+                Some((path, fragment)) => (path, Some(fragment)),
+                None => (rest, None),
+            };
+
+            let (name, version) = match fragment {
+                None => (None, None),
+                // `#0.2.1` only pins the version
+                Some(fragment) if fragment.starts_with(|c: char| c.is_ascii_digit()) => {
+                    (None, Some(fragment.to_string()))
+                }
+                Some(fragment) => {
+                    let (name, version) = split_version(fragment);
+                    (Some(name.to_string()), version)
+                }
+            };
+
+            return Ok(Self::Path {
+                path: PathBuf::from(path),
+                name,
+                version,
+            });
+        }
+
+        if spec.starts_with("path+") {
+            bail!(
+                "invalid package ID specification `{}`, only file URLs are supported",
+                spec
+            );
+        }
+
+        let (name, version) = split_version(spec);
+        let pattern = glob::Pattern::new(name)
+            .map_err(|e| anyhow!("invalid package pattern `{}`: {}", name, e))?;
+        Ok(Self::Name { pattern, version })
+    }
+
+    pub fn matches(&self, package: &Package) -> bool {
+        let (name_matches, version) = match self {
+            Self::Name { pattern, version } => (pattern.matches(&package.name), version),
+            Self::Path {
+                path,
+                name,
+                version,
+            } => {
+                let same_root =
+                    canonicalize_with_strip(path).is_ok_and(|path| path == package.root_path);
+                let same_name = name.as_ref().is_none_or(|name| *name == package.name);
+                (same_root && same_name, version)
+            }
+        };
+
+        name_matches
+            && version
+                .as_ref()
+                .is_none_or(|version| version_matches(version, &package.version))
+    }
+}
+
+fn split_version(spec: &str) -> (&str, Option<String>) {
+    match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version.to_string())),
+        None => (spec, None),
+    }
+}
+
+/// `1.2` matches `1.2.0` and `1.2.7`, but not `1.20.0`.
+pub fn version_matches(spec: &str, version: &str) -> bool {
+    let mut version = version.split(['.', '-', '+']);
+    spec.split('.')
+        .all(|component| version.next() == Some(component))
+}
+
+/// Which packages of a workspace a command operates on.
+#[derive(Debug, Clone, Default)]
+pub struct PackageSelection {
+    /// Every member of the workspace.
+    pub workspace: bool,
+    pub packages: Vec<String>,
+    /// Packages left out when selecting the whole workspace.
+    pub exclude: Vec<String>,
+}
+
+impl PackageSelection {
+    /// Returns the selected packages in workspace member order. Outside of a workspace the
+    /// package itself is selected by default, in a workspace nothing is, so an empty list means
+    /// the command has to decide what to do.
+    pub fn resolve(&self, ctx: &KelpieContext, project_id: ProjectId) -> Result<Vec<PackageId>> {
+        let project = ctx
+            .get_project(project_id)
+            .ok_or_else(|| anyhow!("Failed to get project"))?;
+
+        let (members, in_workspace) = match &project.kind {
+            ProjectKind::Package(package_id) => (vec![*package_id], false),
+            ProjectKind::Workspace(workspace) => (workspace.members.clone(), true),
+        };
+
+        let packages = parse_specs(&self.packages)?;
+        let exclude = parse_specs(&self.exclude)?;
+
+        for (spec, parsed) in &packages {
+            let found = members
+                .iter()
+                .any(|&id| ctx.get_package(id).is_some_and(|pkg| parsed.matches(pkg)));
+            if !found {
+                if in_workspace {
+                    bail!("Package '{}' not found in workspace", spec);
+                }
+                bail!("Package '{}' not found", spec);
+            }
+        }
+
+        if !self.workspace && packages.is_empty() && in_workspace {
+            return Ok(vec![]);
+        }
+
+        let selected = members
+            .into_iter()
+            .filter(|&id| {
+                let Some(package) = ctx.get_package(id) else {
+                    return false;
+                };
+
+                let included = self.workspace
+                    || packages.is_empty()
+                    || packages.iter().any(|(_, spec)| spec.matches(package));
+                included && !exclude.iter().any(|(_, spec)| spec.matches(package))
+            })
+            .collect();
+
+        Ok(selected)
+    }
+}
+
+fn parse_specs(specs: &[String]) -> Result<Vec<(&String, PackageSpec)>> {
+    specs
+        .iter()
+        .map(|spec| Ok((spec, PackageSpec::parse(spec)?)))
+        .collect()
+}
@@ -0,0 +1,440 @@
+use crate::{
+    CONFIG_FILE, INTERFACE_EXTENSION, LibraryType, Project, ProjectKind, TomlConfig,
+    artifact_file_name, find_enclosing_workspace, is_workspace_member, relative_path,
+};
+use anyhow::Result;
+use std::env;
+use std::path::{Path, PathBuf};
+
+pub const TARGET_DIR_ENV: &str = "KELPIE_TARGET_DIR";
+pub const DEFAULT_TARGET_DIR: &str = "target";
+
+/// Where build outputs go:
+///
+/// ```text
+/// target/[<triple>/]<profile>/
+///     deps/         artifacts of dependencies
+///     incremental/  data reused between builds
+///     build/        build script outputs
+///     check/        interfaces written by `kl check`
+///     tests/        test harnesses built by `kl test`
+///     benches/      benchmark harnesses built by `kl bench`
+///     doctests/     examples compiled by `kl test --doc`
+///     bench-results/ results of the last `kl bench` and saved baselines
+///     .fingerprint/ what each package was last compiled from
+///     <outputs>     the binaries and libraries that were asked for
+/// ```
+#[derive(Debug, Clone)]
+pub struct TargetLayout {
+    target_dir: PathBuf,
+    dest: PathBuf,
+}
+
+impl TargetLayout {
+    pub fn new(target_dir: PathBuf, triple: Option<&str>, profile: &str) -> Self {
+        let mut dest = target_dir.clone();
+        if let Some(triple) = triple {
+            dest.push(triple);
+        }
+        dest.push(profile);
+
+        Self { target_dir, dest }
+    }
+
+    /// Layout shared by every package of the workspace `project` belongs to, see
+    /// [`resolve_target_dir`].
+    pub fn for_project(
+        project: &Project,
+        target_dir: Option<&Path>,
+        configured_target_dir: Option<&Path>,
+        triple: Option<&str>,
+        profile: &str,
+    ) -> Result<Self> {
+        let target_dir = resolve_target_dir(project, target_dir, configured_target_dir)?;
+        Ok(Self::new(target_dir, triple, profile))
+    }
+
+    /// Every layout a previous build left in `target_dir`, recognized by their fingerprints.
+    pub fn existing(target_dir: &Path) -> Result<Vec<Self>> {
+        let mut layouts = vec![];
+        let mut candidates = vec![(target_dir.to_path_buf(), 0)];
+
+        while let Some((dir, depth)) = candidates.pop() {
+            if !dir.is_dir() {
+                continue;
+            }
+
+            if dir != target_dir && dir.join(".fingerprint").is_dir() {
+                layouts.push(Self {
+                    target_dir: target_dir.to_path_buf(),
+                    dest: dir,
+                });
+                continue;
+            }
+
+            // `<profile>` or `<triple>/<profile>`
+            if depth < 2 {
+                for entry in fs_err::read_dir(&dir)? {
+                    let entry = entry?;
+                    if entry.file_type()?.is_dir() {
+                        candidates.push((entry.path(), depth + 1));
+                    }
+                }
+            }
+        }
+
+        layouts.sort_by(|a, b| a.dest.cmp(&b.dest));
+        Ok(layouts)
+    }
+
+    pub fn target_dir(&self) -> &Path {
+        &self.target_dir
+    }
+
+    pub fn profile(&self) -> &str {
+        self.dest
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    /// `target/[<triple>/]<profile>`, where the final binaries and libraries are written.
+    pub fn dest(&self) -> &Path {
+        &self.dest
+    }
+
+    pub fn deps(&self) -> PathBuf {
+        self.dest.join("deps")
+    }
+
+    pub fn incremental(&self) -> PathBuf {
+        self.dest.join("incremental")
+    }
+
+    pub fn build(&self) -> PathBuf {
+        self.dest.join("build")
+    }
+
+    /// Interfaces produced by `kl check`, with their own fingerprints.
+    pub fn check(&self) -> PathBuf {
+        self.dest.join("check")
+    }
+
+    /// Test harnesses built by `kl test`, in a directory per package.
+    pub fn tests(&self) -> PathBuf {
+        self.dest.join("tests")
+    }
+
+    /// Sources and binaries of the doc tests run by `kl test --doc`, in a directory per package.
+    pub fn doc_tests(&self) -> PathBuf {
+        self.dest.join("doctests")
+    }
+
+    /// Benchmark harnesses built by `kl bench`, in a directory per package.
+    pub fn benches(&self) -> PathBuf {
+        self.dest.join("benches")
+    }
+
+    /// Results of `kl bench` runs, see [`crate::BenchResults`].
+    pub fn bench_results(&self) -> PathBuf {
+        self.dest.join("bench-results")
+    }
+
+    /// Fingerprints of the packages compiled by the last build.
+    pub fn fingerprint(&self) -> PathBuf {
+        self.dest.join(".fingerprint")
+    }
+
+    /// Everything a build left behind for the package `name`, in any version.
+    pub fn package_files(&self, name: &str) -> Vec<PathBuf> {
+        let mut forms: Vec<String> = [None, Some(LibraryType::Static), Some(LibraryType::Dynamic)]
+            .into_iter()
+            .map(|lib_type| artifact_file_name("{}", lib_type))
+            .collect();
+        forms.push(format!("{{}}.{INTERFACE_EXTENSION}"));
+
+        let mut files = vec![];
+        for dir in [self.dest.clone(), self.deps(), self.check()] {
+            for form in &forms {
+                files.push(dir.join(form.replace("{}", name)));
+                files.extend(versioned_files(&dir, name, form));
+            }
+        }
+
+        for dir in [self.fingerprint(), self.check().join(".fingerprint")] {
+            files.extend(versioned_files(&dir, name, "{}.toml"));
+        }
+        files.push(self.incremental().join(name));
+        files.extend(versioned_files(&self.incremental(), name, "{}"));
+        files.push(self.build().join(name));
+        files.push(self.tests().join(name));
+        files.push(self.fingerprint().join("tests").join(name));
+        files.push(self.benches().join(name));
+        files.push(self.doc_tests().join(name));
+        files.push(self.fingerprint().join("benches").join(name));
+
+        files.retain(|file| file.exists());
+        files
+    }
+
+    pub fn prepare(&self) -> Result<()> {
+        for dir in [
+            self.deps(),
+            self.incremental(),
+            self.build(),
+            self.fingerprint(),
+            self.check().join(".fingerprint"),
+            self.tests(),
+            self.benches(),
+            self.bench_results(),
+        ] {
+            fs_err::create_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Files of every version of the package `name` in `dir`, named like `form` with `{}` standing
+/// for `<name>-<version>`, such as fingerprints with the form `{}.toml`.
+fn versioned_files(dir: &Path, name: &str, form: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs_err::read_dir(dir) else {
+        return vec![];
+    };
+
+    let (prefix, suffix) = form.split_once("{}").unwrap();
+    let prefix = format!("{prefix}{name}-");
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|file_name| file_name.to_str())
+                .and_then(|file_name| file_name.strip_prefix(&prefix))
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .is_some_and(is_version)
+        })
+        .collect()
+}
+
+/// Whether `text` is `<major>.<minor>.<patch>`, possibly followed by a pre-release or build
+/// metadata, so `foo-0.1.0.zrm` isn't taken for a binary of `foo`.
+fn is_version(text: &str) -> bool {
+    let core = text.split(['-', '+']).next().unwrap();
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The target directory is `target_dir` from `--target-dir` if given, then
+/// `configured_target_dir` from kelpie's configuration, which `KELPIE_TARGET_DIR` sets too, then
+/// `[build] target-dir` from the root manifest and finally `target/` next to it.
+pub fn resolve_target_dir(
+    project: &Project,
+    target_dir: Option<&Path>,
+    configured_target_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    let root = workspace_root(project)?;
+    let target_dir = match target_dir.or(configured_target_dir) {
+        Some(dir) => env::current_dir()?.join(dir),
+        None => {
+            let build = TomlConfig::from_path(root.join(CONFIG_FILE))?.build;
+            match build.and_then(|build| build.target_dir) {
+                Some(dir) => root.join(dir),
+                None => root.join(DEFAULT_TARGET_DIR),
+            }
+        }
+    };
+
+    Ok(target_dir)
+}
+
+/// Root directory of the workspace `project` is a member of, or its own root.
+pub fn workspace_root(project: &Project) -> Result<PathBuf> {
+    let root = project.root_path().to_path_buf();
+    if let ProjectKind::Workspace(_) = project.kind {
+        return Ok(root);
+    }
+    package_workspace_root(root)
+}
+
+/// Like [`workspace_root`], from the manifest alone rather than a loaded project, so nothing
+/// is resolved.
+pub fn manifest_workspace_root(manifest_path: &Path) -> Result<PathBuf> {
+    let root = manifest_path.parent().unwrap().to_path_buf();
+    if TomlConfig::from_path(manifest_path)?.workspace.is_some() {
+        return Ok(root);
+    }
+    package_workspace_root(root)
+}
+
+/// The workspace the package at `root` is a member of, or `root` itself.
+fn package_workspace_root(root: PathBuf) -> Result<PathBuf> {
+    let Some(manifest_path) = find_enclosing_workspace(&root)? else {
+        return Ok(root);
+    };
+
+    let workspace_root = manifest_path.parent().unwrap().to_path_buf();
+    let members = TomlConfig::from_path(&manifest_path)?
+        .workspace
+        .and_then(|workspace| workspace.members)
+        .unwrap_or_default();
+
+    if is_workspace_member(&members, &relative_path(&workspace_root, &root)) {
+        Ok(workspace_root)
+    } else {
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::{KelpieContext, find_config, find_manifest};
+
+    #[test]
+    fn layout_paths() {
+        let layout = TargetLayout::new(PathBuf::from("target"), None, "release");
+        assert_eq!(layout.target_dir(), Path::new("target"));
+        assert_eq!(layout.dest(), Path::new("target/release"));
+        assert_eq!(layout.profile(), "release");
+        assert_eq!(layout.deps(), Path::new("target/release/deps"));
+        assert_eq!(layout.check(), Path::new("target/release/check"));
+        assert_eq!(layout.tests(), Path::new("target/release/tests"));
+        assert_eq!(
+            layout.fingerprint(),
+            Path::new("target/release/.fingerprint")
+        );
+
+        let cross = TargetLayout::new(PathBuf::from("target"), Some("wasm32-wasi"), "debug");
+        assert_eq!(cross.dest(), Path::new("target/wasm32-wasi/debug"));
+        assert_eq!(cross.profile(), "debug");
+        assert_eq!(cross.deps(), Path::new("target/wasm32-wasi/debug/deps"));
+    }
+
+    #[test]
+    fn existing_finds_prepared_layouts() {
+        let fixture = Fixture::new(&[]);
+        let target_dir = fixture.path().join("target");
+        TargetLayout::new(target_dir.clone(), None, "release")
+            .prepare()
+            .unwrap();
+        TargetLayout::new(target_dir.clone(), Some("wasm32-wasi"), "debug")
+            .prepare()
+            .unwrap();
+        fs_err::create_dir_all(target_dir.join("unrelated")).unwrap();
+
+        let dests: Vec<PathBuf> = TargetLayout::existing(&target_dir)
+            .unwrap()
+            .iter()
+            .map(|layout| layout.dest().to_path_buf())
+            .collect();
+        assert_eq!(
+            dests,
+            [
+                target_dir.join("release"),
+                target_dir.join("wasm32-wasi").join("debug")
+            ]
+        );
+    }
+
+    #[test]
+    fn members_share_the_target_dir_of_the_workspace() {
+        let fixture = Fixture::new(&[
+            (
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["a"]
+
+                [build]
+                target-dir = "out"
+                "#,
+            ),
+            (
+                "a/config.toml",
+                r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+                "#,
+            ),
+            ("a/src/lib.zr", ""),
+        ]);
+        let mut ctx = KelpieContext::new();
+        let project_id = find_config(fixture.path().join("a"), &mut ctx).unwrap();
+        let project = ctx.get_project(project_id).unwrap();
+
+        let layout = TargetLayout::for_project(project, None, None, None, "debug").unwrap();
+        assert_eq!(layout.dest(), fixture.root().join("out").join("debug"));
+    }
+
+    #[test]
+    fn target_dir_argument_wins_over_the_configuration_and_the_manifest() {
+        let fixture = Fixture::new(&[
+            (
+                "config.toml",
+                r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [build]
+                target-dir = "out"
+                "#,
+            ),
+            ("src/lib.zr", ""),
+        ]);
+        let (ctx, project_id) = fixture.load();
+        let project = ctx.get_project(project_id).unwrap();
+        let root = fixture.root();
+        let (cli, configured) = (root.join("cli"), root.join("configured"));
+
+        let resolve = |cli: Option<&Path>, configured: Option<&Path>| {
+            resolve_target_dir(project, cli, configured).unwrap()
+        };
+        assert_eq!(resolve(Some(&cli), Some(&configured)), cli);
+        assert_eq!(resolve(None, Some(&configured)), configured);
+        assert_eq!(resolve(None, None), root.join("out"));
+    }
+
+    #[test]
+    fn manifests_give_the_workspace_root_of_their_project() {
+        let fixture = Fixture::new(&[
+            (
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["a"]
+                "#,
+            ),
+            (
+                "a/config.toml",
+                r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+                "#,
+            ),
+            ("a/src/lib.zr", ""),
+            (
+                "b/config.toml",
+                r#"
+                [package]
+                name = "b"
+                version = "0.1.0"
+                "#,
+            ),
+            ("b/src/lib.zr", ""),
+        ]);
+        let root = fixture.root();
+
+        for (dir, expected) in [("", &root), ("a", &root), ("b", &root.join("b"))] {
+            let manifest_path = find_manifest(root.join(dir)).unwrap().unwrap();
+            assert_eq!(manifest_path, root.join(dir).join(CONFIG_FILE));
+            assert_eq!(manifest_workspace_root(&manifest_path).unwrap(), *expected);
+        }
+    }
+}
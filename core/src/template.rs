@@ -0,0 +1,299 @@
+use crate::{GitReference, clone_repository, kelpie_home};
+use anyhow::{Result, bail};
+use serde_derive::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const TEMPLATE_MANIFEST: &str = "template.toml";
+pub const TEMPLATES_DIR: &str = "templates";
+
+#[derive(Deserialize, Debug, Default)]
+struct TomlTemplate {
+    template: Option<TomlTemplateInfo>,
+    placeholders: Option<BTreeMap<String, TomlPlaceholder>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TomlTemplateInfo {
+    description: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TomlPlaceholder {
+    prompt: Option<String>,
+    default: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Placeholder {
+    pub name: String,
+    pub prompt: String,
+    pub default: Option<String>,
+}
+
+/// A directory whose files are copied into a new package with `{{placeholder}}`s substituted.
+#[derive(Debug)]
+pub struct Template {
+    pub root: PathBuf,
+    pub description: Option<String>,
+    pub placeholders: Vec<Placeholder>,
+    /// Temporary checkout of a git template, removed when the template is dropped.
+    checkout: Option<PathBuf>,
+}
+
+impl Template {
+    /// Resolves a template given as a git URL, a path or the name of a template in the user's
+    /// kelpie directory.
+    pub fn resolve(spec: &str) -> Result<Self> {
+        if is_git_url(spec) {
+            let checkout = std::env::temp_dir().join(format!(
+                "kelpie-template-{}-{}",
+                std::process::id(),
+                SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos()
+            ));
+            let result = clone_repository(spec, &checkout, GitReference::DefaultBranch)
+                .and_then(|()| Self::load(checkout.clone()));
+            if result.is_err() {
+                let _ = fs_err::remove_dir_all(&checkout);
+            }
+
+            let mut template = result?;
+            template.checkout = Some(checkout);
+            return Ok(template);
+        }
+
+        let path = Path::new(spec);
+        if path.is_dir() {
+            return Self::load(path.to_path_buf());
+        }
+
+        let templates_dir = kelpie_home()?.join(TEMPLATES_DIR);
+        let root = templates_dir.join(spec);
+        if !root.is_dir() {
+            let available = Self::available()?;
+            if available.is_empty() {
+                bail!(
+                    "template `{}` not found, no templates are installed in {}",
+                    spec,
+                    templates_dir.display()
+                );
+            }
+
+            let names: Vec<_> = available.into_iter().map(|(name, _)| name).collect();
+            bail!(
+                "template `{}` not found, available templates: {}",
+                spec,
+                names.join(", ")
+            );
+        }
+
+        Self::load(root)
+    }
+
+    pub fn load(root: PathBuf) -> Result<Self> {
+        let manifest_path = root.join(TEMPLATE_MANIFEST);
+        let toml: TomlTemplate = if manifest_path.exists() {
+            toml::from_str(&fs_err::read_to_string(&manifest_path)?)?
+        } else {
+            TomlTemplate::default()
+        };
+
+        let placeholders = toml
+            .placeholders
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, placeholder)| Placeholder {
+                prompt: placeholder.prompt.unwrap_or_else(|| name.clone()),
+                default: placeholder.default,
+                name,
+            })
+            .collect();
+
+        Ok(Self {
+            root,
+            description: toml.template.and_then(|t| t.description),
+            placeholders,
+            checkout: None,
+        })
+    }
+
+    /// Lists the templates installed in the user's kelpie directory with their descriptions.
+    pub fn available() -> Result<Vec<(String, Option<String>)>> {
+        let templates_dir = kelpie_home()?.join(TEMPLATES_DIR);
+        if !templates_dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut templates = vec![];
+        for entry in fs_err::read_dir(&templates_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let description = Self::load(entry.path())?.description.clone();
+            templates.push((name, description));
+        }
+        templates.sort();
+
+        Ok(templates)
+    }
+
+    /// Copies the template into `dest`, skipping files that already exist there.
+    pub fn render(&self, dest: &Path, values: &HashMap<String, String>) -> Result<()> {
+        render_dir(&self.root, dest, values, true)
+    }
+}
+
+impl Drop for Template {
+    fn drop(&mut self) {
+        if let Some(checkout) = &self.checkout {
+            let _ = fs_err::remove_dir_all(checkout);
+        }
+    }
+}
+
+fn render_dir(
+    src: &Path,
+    dest: &Path,
+    values: &HashMap<String, String>,
+    is_root: bool,
+) -> Result<()> {
+    fs_err::create_dir_all(dest)?;
+
+    for entry in fs_err::read_dir(src)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name == ".git" || (is_root && file_name == TEMPLATE_MANIFEST) {
+            continue;
+        }
+
+        let target = dest.join(substitute(&file_name, values));
+        if entry.file_type()?.is_dir() {
+            render_dir(&entry.path(), &target, values, false)?;
+        } else if !target.exists() {
+            let bytes = fs_err::read(entry.path())?;
+            match String::from_utf8(bytes) {
+                Ok(text) => fs_err::write(&target, substitute(&text, values))?,
+                Err(e) => fs_err::write(&target, e.into_bytes())?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces `{{key}}` (or `{{ key }}`) with the matching value, unknown keys are kept as-is.
+pub fn substitute(text: &str, values: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let key = after[..end].trim();
+        match values.get(key) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn is_git_url(spec: &str) -> bool {
+    spec.contains("://") || spec.starts_with("git@") || spec.ends_with(".git")
+}
+
+/// Values every template can use: `name`, `author` and `year`.
+pub fn builtin_template_values(name: &str) -> HashMap<String, String> {
+    HashMap::from([
+        ("name".to_string(), name.to_string()),
+        ("author".to_string(), default_author()),
+        ("year".to_string(), current_year().to_string()),
+    ])
+}
+
+fn default_author() -> String {
+    let git_name = Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|name| !name.is_empty());
+
+    git_name
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .unwrap_or_default()
+}
+
+fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default() as i64;
+
+    // civil-from-days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+    yoe + era * 400 + i64::from(month <= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn failed_git_templates_leave_no_checkout() {
+        let fixture = Fixture::new(&[("template.toml", "[template\n")]);
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=kelpie",
+                    "-c",
+                    "user.email=kelpie@localhost",
+                ])
+                .args(args)
+                .current_dir(fixture.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "initial"]);
+
+        let url = format!("file://{}", fixture.root().display());
+        assert!(Template::resolve(&url).is_err());
+
+        let prefix = format!("kelpie-template-{}-", std::process::id());
+        let leftovers: Vec<_> = fs_err::read_dir(std::env::temp_dir())
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{TestBinary, TestCase};
+use anyhow::{Result, anyhow, bail};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zirael_core::prelude::debug;
+
+/// Name under which the results of every `kl bench` run are stored.
+pub const LATEST_BENCH_RESULTS: &str = "latest";
+
+impl TestBinary {
+    /// Runs the benchmark `case` from `cwd` with `--bench --exact <name>`. The harness prints a
+    /// `<name>: <nanoseconds> ns/iter` line with the time an iteration took.
+    pub fn bench(&self, case: &TestCase, cwd: &Path) -> Result<f64> {
+        let mut cmd = Command::new(&self.path);
+        cmd.arg("--bench")
+            .arg("--exact")
+            .arg(&case.name)
+            .current_dir(cwd);
+        debug!("{:?}", cmd);
+
+        let output = cmd.output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            bail!(
+                "benchmark {} failed\n{}{}",
+                case.name,
+                stdout,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix(&case.name)?.strip_prefix(": "))
+            .find_map(|time| time.strip_suffix(" ns/iter")?.trim().parse().ok())
+            .ok_or_else(|| anyhow!("benchmark {} didn't report its time", case.name))
+    }
+}
+
+/// Nanoseconds per iteration of each benchmark, keyed by `<harness>::<benchmark>`. Stored as
+/// `bench-results/<name>.toml` in the target directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BenchResults {
+    pub results: BTreeMap<String, f64>,
+}
+
+impl BenchResults {
+    pub fn path(dir: &Path, name: &str) -> Result<PathBuf> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            bail!("invalid baseline name `{name}`");
+        }
+        Ok(dir.join(format!("{name}.toml")))
+    }
+
+    pub fn load(dir: &Path, name: &str) -> Result<Option<Self>> {
+        let path = Self::path(dir, name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(toml::from_str(&fs_err::read_to_string(path)?)?))
+    }
+
+    /// Saves the results as `name`. Of the results saved before, only those of benchmarks that
+    /// didn't run and for which `keep` returns true are kept, so removed benchmarks don't linger.
+    pub fn save(&self, dir: &Path, name: &str, keep: impl Fn(&str) -> bool) -> Result<()> {
+        let mut saved = Self::load(dir, name)?.unwrap_or_default();
+        saved.results.retain(|key, _| keep(key));
+        saved.results.extend(self.results.clone());
+
+        fs_err::create_dir_all(dir)?;
+        fs_err::write(Self::path(dir, name)?, toml::to_string(&saved)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchChange {
+    Improved,
+    Regressed,
+    /// Within the noise threshold.
+    Unchanged,
+}
+
+/// How a benchmark did compared to a baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchComparison {
+    /// Change of the time per iteration in percent, positive when it got slower.
+    pub percent: f64,
+    pub change: BenchChange,
+}
+
+impl BenchComparison {
+    /// Changes of at most `threshold` percent either way are considered noise.
+    pub fn new(baseline: f64, current: f64, threshold: f64) -> Self {
+        let percent = if baseline == 0.0 {
+            0.0
+        } else {
+            (current - baseline) / baseline * 100.0
+        };
+
+        let change = if percent > threshold {
+            BenchChange::Regressed
+        } else if percent < -threshold {
+            BenchChange::Improved
+        } else {
+            BenchChange::Unchanged
+        };
+
+        Self { percent, change }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn results(results: &[(&str, f64)]) -> BenchResults {
+        BenchResults {
+            results: results
+                .iter()
+                .map(|&(key, time)| (key.to_string(), time))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = TempDir::new("kelpie-bench").unwrap();
+        let dir = dir.path().join("bench-results");
+        assert!(BenchResults::load(&dir, "main").unwrap().is_none());
+
+        results(&[("foo::a", 12.5), ("foo-b::b", 3.0)])
+            .save(&dir, "main", |_| true)
+            .unwrap();
+        let loaded = BenchResults::load(&dir, "main").unwrap().unwrap();
+        assert_eq!(
+            loaded.results,
+            results(&[("foo::a", 12.5), ("foo-b::b", 3.0)]).results
+        );
+    }
+
+    #[test]
+    fn save_only_keeps_what_keep_accepts() {
+        let dir = TempDir::new("kelpie-bench").unwrap();
+        results(&[("foo::a", 1.0), ("foo::removed", 2.0), ("bar::c", 3.0)])
+            .save(dir.path(), "main", |_| true)
+            .unwrap();
+
+        results(&[("foo::a", 4.0)])
+            .save(dir.path(), "main", |key| !key.starts_with("foo::"))
+            .unwrap();
+        let loaded = BenchResults::load(dir.path(), "main").unwrap().unwrap();
+        assert_eq!(
+            loaded.results,
+            results(&[("bar::c", 3.0), ("foo::a", 4.0)]).results
+        );
+    }
+
+    #[test]
+    fn invalid_names() {
+        for name in ["", "a/b", "a\\b", ".hidden"] {
+            assert!(
+                BenchResults::path(Path::new("dir"), name).is_err(),
+                "{name}"
+            );
+        }
+        assert_eq!(
+            BenchResults::path(Path::new("dir"), "main").unwrap(),
+            Path::new("dir/main.toml")
+        );
+    }
+
+    #[test]
+    fn comparison() {
+        let compare = |baseline, current| BenchComparison::new(baseline, current, 5.0);
+
+        assert_eq!(
+            compare(100.0, 110.0),
+            BenchComparison {
+                percent: 10.0,
+                change: BenchChange::Regressed
+            }
+        );
+        assert_eq!(
+            compare(100.0, 80.0),
+            BenchComparison {
+                percent: -20.0,
+                change: BenchChange::Improved
+            }
+        );
+        assert_eq!(compare(100.0, 105.0).change, BenchChange::Unchanged);
+        assert_eq!(compare(100.0, 95.0).change, BenchChange::Unchanged);
+        assert_eq!(compare(0.0, 10.0).percent, 0.0);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{Package, TEST_EXTENSION, relative_path};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Markdown files next to the manifest whose examples are tested too.
+pub const DOC_TEST_MARKDOWN: &[&str] = &["README.md"];
+
+/// A fenced Zirael code block from a doc comment or markdown file, compiled into a binary of its
+/// own. In doc comments blocks without an info string are Zirael too, like ```` ```zirael ````,
+/// while markdown files only have their blocks tagged `zirael` or `zr` tested. Comma-separated
+/// annotations after the language change how they are tested:
+///
+/// - `ignore`: the example isn't compiled
+/// - `no_run`: the example is compiled but not run
+/// - `should_fail`: compiling or running the example has to fail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocTest {
+    /// `<file> (line <n>)`, relative to the package root.
+    pub name: String,
+    pub code: String,
+    pub ignore: bool,
+    pub no_run: bool,
+    pub should_fail: bool,
+}
+
+impl DocTest {
+    /// The source of the example's binary. Lines starting with `# ` are hidden from the
+    /// documentation but still compiled, and examples that don't define `fn main(` at the start
+    /// of a line are wrapped in a `main` function.
+    pub fn program(&self) -> String {
+        let mut code = String::new();
+        for line in self.code.lines() {
+            let line = match line.trim_start() {
+                "#" => "",
+                trimmed => trimmed.strip_prefix("# ").unwrap_or(line),
+            };
+            code.push_str(line);
+            code.push('\n');
+        }
+
+        if code.lines().any(|line| line.starts_with("fn main(")) {
+            code
+        } else {
+            format!("fn main() {{\n{code}}}\n")
+        }
+    }
+}
+
+/// Examples from the doc comments (`///` and `//!`) of every source file next to the
+/// entrypoint, then from the markdown files in [`DOC_TEST_MARKDOWN`].
+pub fn extract_doc_tests(package: &Package) -> Result<Vec<DocTest>> {
+    let mut tests = vec![];
+
+    let entrypoint = package.full_entrypoint_path();
+    if let Some(source_dir) = entrypoint.parent() {
+        for source in source_files(source_dir)? {
+            let contents = fs_err::read_to_string(&source)?;
+            let name = relative_path(&package.root_path, &source);
+
+            let mut comment = vec![];
+            for (index, line) in contents.lines().enumerate() {
+                match doc_comment_text(line) {
+                    Some(text) => comment.push((index + 1, text)),
+                    None if !comment.is_empty() => {
+                        tests.extend(fenced_blocks(&name, &comment, true));
+                        comment.clear();
+                    }
+                    None => {}
+                }
+            }
+            tests.extend(fenced_blocks(&name, &comment, true));
+        }
+    }
+
+    for markdown in DOC_TEST_MARKDOWN {
+        let path = package.root_path.join(markdown);
+        if !path.is_file() {
+            continue;
+        }
+
+        let contents = fs_err::read_to_string(&path)?;
+        let lines: Vec<_> = contents
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .collect();
+        tests.extend(fenced_blocks(Path::new(markdown), &lines, false));
+    }
+
+    Ok(tests)
+}
+
+/// Every `.zr` file in `dir` and its subdirectories, sorted.
+fn source_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs_err::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == TEST_EXTENSION) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn doc_comment_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let text = trimmed.strip_prefix("//!").or_else(|| {
+        trimmed
+            .strip_prefix("///")
+            .filter(|text| !text.starts_with('/'))
+    })?;
+    Some(text.strip_prefix(' ').unwrap_or(text))
+}
+
+/// The Zirael blocks among `lines`, numbered from 1. Blocks without a language are only Zirael
+/// when `untagged_is_zirael` is set.
+fn fenced_blocks(file: &Path, lines: &[(usize, &str)], untagged_is_zirael: bool) -> Vec<DocTest> {
+    let mut tests = vec![];
+    let mut lines = lines.iter();
+
+    while let Some((line_number, line)) = lines.next() {
+        let trimmed = line.trim_start();
+        let Some(fence) = ["```", "~~~"]
+            .into_iter()
+            .find(|fence| trimmed.starts_with(fence))
+        else {
+            continue;
+        };
+
+        let mut code = String::new();
+        for (_, line) in lines.by_ref() {
+            if line.trim_start().starts_with(fence) {
+                break;
+            }
+            code.push_str(line);
+            code.push('\n');
+        }
+
+        let mut test = DocTest {
+            name: format!("{} (line {})", file.display(), line_number),
+            code,
+            ignore: false,
+            no_run: false,
+            should_fail: false,
+        };
+        let mut is_zirael = true;
+        let mut tagged = false;
+        for annotation in trimmed[fence.len()..]
+            .split([',', ' ', '\t'])
+            .filter(|annotation| !annotation.is_empty())
+        {
+            match annotation {
+                "zirael" | "zr" => tagged = true,
+                "ignore" => test.ignore = true,
+                "no_run" => test.no_run = true,
+                "should_fail" => test.should_fail = true,
+                _ => is_zirael = false,
+            }
+        }
+
+        if is_zirael && (tagged || untagged_is_zirael) {
+            tests.push(test);
+        }
+    }
+
+    tests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(text: &str) -> Vec<(usize, &str)> {
+        text.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .collect()
+    }
+
+    fn doc_test(code: &str) -> DocTest {
+        DocTest {
+            name: "src/lib.zr (line 1)".to_string(),
+            code: code.to_string(),
+            ignore: false,
+            no_run: false,
+            should_fail: false,
+        }
+    }
+
+    #[test]
+    fn doc_comments() {
+        assert_eq!(doc_comment_text("/// text"), Some("text"));
+        assert_eq!(doc_comment_text("    ///  indented"), Some(" indented"));
+        assert_eq!(doc_comment_text("//! inner"), Some("inner"));
+        assert_eq!(doc_comment_text("///"), Some(""));
+        assert_eq!(doc_comment_text("//// separator"), None);
+        assert_eq!(doc_comment_text("// comment"), None);
+        assert_eq!(doc_comment_text("let a = 1; /// trailing"), None);
+    }
+
+    #[test]
+    fn blocks_and_annotations() {
+        let lines = numbered(
+            "text\n```\nlet a = 1;\n```\n~~~zr,no_run\nloop {}\n~~~\n\
+             ``` zirael, should_fail ignore\nbad\n```\n```text\nnot code\n```",
+        );
+        let tests = fenced_blocks(Path::new("src/lib.zr"), &lines, true);
+
+        let summary: Vec<_> = tests
+            .iter()
+            .map(|test| {
+                (
+                    test.name.as_str(),
+                    test.code.as_str(),
+                    test.ignore,
+                    test.no_run,
+                    test.should_fail,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("src/lib.zr (line 2)", "let a = 1;\n", false, false, false),
+                ("src/lib.zr (line 5)", "loop {}\n", false, true, false),
+                ("src/lib.zr (line 8)", "bad\n", true, false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn untagged_blocks_are_skipped_when_asked() {
+        let lines = numbered("```\n$ kl build\n```\n```zirael\nlet a = 1;\n```");
+        let tests = fenced_blocks(Path::new("README.md"), &lines, false);
+
+        assert_eq!(tests.len(), 1);
+        assert_eq!(tests[0].name, "README.md (line 4)");
+    }
+
+    #[test]
+    fn unclosed_block_runs_to_the_end() {
+        let lines = numbered("```\nlet a = 1;");
+        let tests = fenced_blocks(Path::new("src/lib.zr"), &lines, true);
+
+        assert_eq!(tests[0].code, "let a = 1;\n");
+    }
+
+    #[test]
+    fn program_wraps_code_without_main() {
+        assert_eq!(
+            doc_test("# let a = 1;\nprint(a);\n#\n").program(),
+            "fn main() {\nlet a = 1;\nprint(a);\n\n}\n"
+        );
+    }
+
+    #[test]
+    fn program_keeps_its_own_main() {
+        let code = "fn helper() {}\n# fn main() {\nhelper();\n# }\n";
+        assert_eq!(
+            doc_test(code).program(),
+            "fn helper() {}\nfn main() {\nhelper();\n}\n"
+        );
+    }
+
+    #[test]
+    fn mentioning_main_is_not_defining_it() {
+        for code in [
+            "fn main_loop() {}\n",
+            "print(\"fn main()\");\n",
+            "    fn main() {}\n",
+        ] {
+            assert!(
+                doc_test(code).program().starts_with("fn main() {\n"),
+                "{code}"
+            );
+        }
+    }
+}
@@ -0,0 +1,313 @@
+mod bench;
+mod doctest;
+mod report;
+mod shard;
+
+pub use bench::*;
+pub use doctest::*;
+pub use report::*;
+pub use shard::*;
+
+use crate::{Package, PackageId};
+use anyhow::{Result, bail};
+use serde::Serializer;
+use serde_derive::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use zirael_core::prelude::debug;
+
+pub const TESTS_DIR: &str = "tests";
+pub const BENCHES_DIR: &str = "benches";
+pub const TEST_EXTENSION: &str = "zr";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestTargetKind {
+    /// Tests inside the package sources, compiled from its entrypoint.
+    Unit,
+    /// A file in the package's `tests/` directory, which uses the package like a dependent would.
+    Integration,
+    /// A file in the package's `benches/` directory, used like integration tests.
+    Bench,
+    /// Examples in the documentation, see [`DocTest`].
+    Doc,
+}
+
+/// A source file compiled into a test harness.
+#[derive(Debug, Clone)]
+pub struct TestTarget {
+    pub package: PackageId,
+    pub name: String,
+    pub kind: TestTargetKind,
+    pub source: PathBuf,
+}
+
+impl TestTarget {
+    /// Name of the harness binary, unique within the workspace.
+    pub fn binary_name(&self, package: &Package) -> String {
+        match self.kind {
+            TestTargetKind::Unit => package.name.clone(),
+            TestTargetKind::Integration | TestTargetKind::Bench => {
+                format!("{}-{}", package.name, self.name)
+            }
+            TestTargetKind::Doc => format!("{}-doctests", package.name),
+        }
+    }
+}
+
+/// The unit tests of `package` followed by one target per `tests/*.zr` file, sorted by name.
+pub fn discover_test_targets(package_id: PackageId, package: &Package) -> Result<Vec<TestTarget>> {
+    let mut targets = vec![TestTarget {
+        package: package_id,
+        name: package.name.clone(),
+        kind: TestTargetKind::Unit,
+        source: package.full_entrypoint_path(),
+    }];
+    targets.extend(targets_in(
+        package_id,
+        &package.root_path.join(TESTS_DIR),
+        TestTargetKind::Integration,
+    )?);
+
+    Ok(targets)
+}
+
+/// One target per `benches/*.zr` file of `package`, sorted by name.
+pub fn discover_bench_targets(package_id: PackageId, package: &Package) -> Result<Vec<TestTarget>> {
+    targets_in(
+        package_id,
+        &package.root_path.join(BENCHES_DIR),
+        TestTargetKind::Bench,
+    )
+}
+
+fn targets_in(package_id: PackageId, dir: &Path, kind: TestTargetKind) -> Result<Vec<TestTarget>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut targets = vec![];
+    for entry in fs_err::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != TEST_EXTENSION) {
+            continue;
+        }
+
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        targets.push(TestTarget {
+            package: package_id,
+            name,
+            kind,
+            source: path,
+        });
+    }
+    targets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(targets)
+}
+
+/// A compiled test harness. Harnesses list their tests with `--list`, printing a
+/// `<name>: test` line per test (`<name>: ignored` for ignored ones), and run a single test
+/// with `--exact <name>`, exiting with a non-zero code when it fails.
+#[derive(Debug, Clone)]
+pub struct TestBinary {
+    pub target: TestTarget,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: String,
+    pub ignored: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+impl TestOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Passed => "ok",
+            Self::Failed => "FAILED",
+            Self::Ignored => "ignored",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: TestOutcome,
+    /// Serialized as seconds.
+    #[serde(serialize_with = "serialize_seconds")]
+    pub duration: Duration,
+    /// Everything the test printed, only captured for tests that ran.
+    pub output: String,
+}
+
+impl TestBinary {
+    pub fn list(&self) -> Result<Vec<TestCase>> {
+        let mut cmd = Command::new(&self.path);
+        cmd.arg("--list");
+        debug!("{:?}", cmd);
+
+        let output = cmd.output()?;
+        if !output.status.success() {
+            bail!("failed to list the tests in {}", self.path.display());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut cases = vec![];
+        for line in stdout.lines() {
+            let Some((name, kind)) = line.rsplit_once(": ") else {
+                continue;
+            };
+
+            cases.push(TestCase {
+                name: name.to_string(),
+                ignored: kind.trim() == "ignored",
+            });
+        }
+        Ok(cases)
+    }
+
+    /// Runs `case` from `cwd`, ignored tests are reported without running them.
+    pub fn run(&self, case: &TestCase, cwd: &Path) -> Result<TestResult> {
+        if case.ignored {
+            return Ok(TestResult {
+                name: case.name.clone(),
+                outcome: TestOutcome::Ignored,
+                duration: Duration::ZERO,
+                output: String::new(),
+            });
+        }
+
+        let mut cmd = Command::new(&self.path);
+        cmd.arg("--exact").arg(&case.name).current_dir(cwd);
+        debug!("{:?}", cmd);
+
+        let start = Instant::now();
+        let output = cmd.output()?;
+        let duration = start.elapsed();
+
+        let mut captured = String::from_utf8_lossy(&output.stdout).into_owned();
+        captured.push_str(&String::from_utf8_lossy(&output.stderr));
+
+        Ok(TestResult {
+            name: case.name.clone(),
+            outcome: if output.status.success() {
+                TestOutcome::Passed
+            } else {
+                TestOutcome::Failed
+            },
+            duration,
+            output: captured,
+        })
+    }
+}
+
+/// Selects tests whose name contains one of the patterns, or equals one with `exact`. No
+/// patterns select every test.
+#[derive(Debug, Clone, Default)]
+pub struct TestFilter {
+    pub patterns: Vec<String>,
+    pub exact: bool,
+}
+
+impl TestFilter {
+    pub fn matches(&self, name: &str) -> bool {
+        self.patterns.is_empty()
+            || self.patterns.iter().any(|pattern| {
+                if self.exact {
+                    name == pattern
+                } else {
+                    name.contains(pattern.as_str())
+                }
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+}
+
+impl TestSummary {
+    pub fn add(&mut self, outcome: TestOutcome) {
+        match outcome {
+            TestOutcome::Passed => self.passed += 1,
+            TestOutcome::Failed => self.failed += 1,
+            TestOutcome::Ignored => self.ignored += 1,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+fn serialize_seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_matches_substrings() {
+        let filter = TestFilter {
+            patterns: vec!["parse".to_string(), "io::".to_string()],
+            exact: false,
+        };
+        assert!(filter.matches("parse_number"));
+        assert!(filter.matches("io::read"));
+        assert!(!filter.matches("render"));
+
+        assert!(TestFilter::default().matches("anything"));
+    }
+
+    #[test]
+    fn exact_filter_matches_whole_names() {
+        let filter = TestFilter {
+            patterns: vec!["parse".to_string()],
+            exact: true,
+        };
+        assert!(filter.matches("parse"));
+        assert!(!filter.matches("parse_number"));
+    }
+
+    #[test]
+    fn summary_counts_outcomes() {
+        let mut summary = TestSummary::default();
+        assert!(summary.is_success());
+
+        for outcome in [
+            TestOutcome::Passed,
+            TestOutcome::Passed,
+            TestOutcome::Ignored,
+        ] {
+            summary.add(outcome);
+        }
+        assert!(summary.is_success());
+
+        summary.add(TestOutcome::Failed);
+        assert_eq!(
+            summary,
+            TestSummary {
+                passed: 2,
+                failed: 1,
+                ignored: 1
+            }
+        );
+        assert!(!summary.is_success());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{TestOutcome, TestResult, TestSummary, TestTargetKind};
+use anyhow::{Result, bail};
+use serde_derive::Serialize;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Results of a `kl test` run, which reporters turn into files for other tools.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestReport {
+    pub suites: Vec<TestSuite>,
+}
+
+/// The results of one test harness.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestSuite {
+    pub package: String,
+    /// Name of the harness binary, see [`crate::TestTarget::binary_name`].
+    pub name: String,
+    pub kind: TestTargetKind,
+    pub results: Vec<TestResult>,
+}
+
+impl TestSuite {
+    pub fn summary(&self) -> TestSummary {
+        let mut summary = TestSummary::default();
+        for result in &self.results {
+            summary.add(result.outcome);
+        }
+        summary
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.results.iter().map(|result| result.duration).sum()
+    }
+}
+
+impl TestReport {
+    pub fn summary(&self) -> TestSummary {
+        let mut summary = TestSummary::default();
+        for result in self.suites.iter().flat_map(|suite| &suite.results) {
+            summary.add(result.outcome);
+        }
+        summary
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.suites.iter().map(TestSuite::duration).sum()
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        match format {
+            ReportFormat::Json => Ok(serde_json::to_string_pretty(self)? + "\n"),
+            ReportFormat::Junit => Ok(self.junit()),
+        }
+    }
+
+    /// JUnit XML, with a `<testsuite>` per harness named `<package>::<harness>`.
+    fn junit(&self) -> String {
+        let summary = self.summary();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites name=\"kelpie\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            summary.passed + summary.failed + summary.ignored,
+            summary.failed,
+            summary.ignored,
+            self.duration().as_secs_f64()
+        );
+
+        for suite in &self.suites {
+            let summary = suite.summary();
+            let name = escape_xml(&format!("{}::{}", suite.package, suite.name));
+            let _ = writeln!(
+                xml,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+                name,
+                suite.results.len(),
+                summary.failed,
+                summary.ignored,
+                suite.duration().as_secs_f64()
+            );
+
+            for result in &suite.results {
+                let _ = write!(
+                    xml,
+                    "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                    name,
+                    escape_xml(&result.name),
+                    result.duration.as_secs_f64()
+                );
+
+                match result.outcome {
+                    TestOutcome::Passed if result.output.is_empty() => xml.push_str("/>\n"),
+                    TestOutcome::Passed => {
+                        let _ = writeln!(
+                            xml,
+                            ">\n      <system-out>{}</system-out>\n    </testcase>",
+                            escape_xml(&result.output)
+                        );
+                    }
+                    TestOutcome::Failed => {
+                        let _ = writeln!(
+                            xml,
+                            ">\n      <failure message=\"test failed\">{}</failure>\n    </testcase>",
+                            escape_xml(&result.output)
+                        );
+                    }
+                    TestOutcome::Ignored => {
+                        xml.push_str(">\n      <skipped/>\n    </testcase>\n");
+                    }
+                }
+            }
+
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Junit,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "junit" => Ok(Self::Junit),
+            "json" => Ok(Self::Json),
+            _ => bail!("unknown report format `{s}`, expected `junit` or `json`"),
+        }
+    }
+}
+
+/// Escapes `text` for XML and HTML text and attribute values.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // not allowed anywhere in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, outcome: TestOutcome, millis: u64, output: &str) -> TestResult {
+        TestResult {
+            name: name.to_string(),
+            outcome,
+            duration: Duration::from_millis(millis),
+            output: output.to_string(),
+        }
+    }
+
+    fn report() -> TestReport {
+        TestReport {
+            suites: vec![TestSuite {
+                package: "foo".to_string(),
+                name: "foo-<unit>".to_string(),
+                kind: TestTargetKind::Unit,
+                results: vec![
+                    result("adds", TestOutcome::Passed, 1500, ""),
+                    result("prints", TestOutcome::Passed, 0, "a & b\n"),
+                    result(
+                        "compares<T>",
+                        TestOutcome::Failed,
+                        250,
+                        "\"1\" != '2'\u{1b}[0m",
+                    ),
+                    result("slow", TestOutcome::Ignored, 0, ""),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn junit() {
+        assert_eq!(
+            report().render(ReportFormat::Junit).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="kelpie" tests="4" failures="1" skipped="1" time="1.750">
+  <testsuite name="foo::foo-&lt;unit&gt;" tests="4" failures="1" skipped="1" time="1.750">
+    <testcase classname="foo::foo-&lt;unit&gt;" name="adds" time="1.500"/>
+    <testcase classname="foo::foo-&lt;unit&gt;" name="prints" time="0.000">
+      <system-out>a &amp; b
+</system-out>
+    </testcase>
+    <testcase classname="foo::foo-&lt;unit&gt;" name="compares&lt;T&gt;" time="0.250">
+      <failure message="test failed">&quot;1&quot; != &apos;2&apos;[0m</failure>
+    </testcase>
+    <testcase classname="foo::foo-&lt;unit&gt;" name="slow" time="0.000">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&report().render(ReportFormat::Json).unwrap()).unwrap();
+        let suite = &json["suites"][0];
+
+        assert_eq!(suite["package"], "foo");
+        assert_eq!(suite["kind"], "unit");
+        assert_eq!(suite["results"][0]["duration"], 1.5);
+        assert_eq!(suite["results"][2]["name"], "compares<T>");
+        assert_eq!(suite["results"][2]["outcome"], "failed");
+        assert_eq!(suite["results"][2]["output"], "\"1\" != '2'\u{1b}[0m");
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(
+            "junit".parse::<ReportFormat>().unwrap(),
+            ReportFormat::Junit
+        );
+        assert_eq!("json".parse::<ReportFormat>().unwrap(), ReportFormat::Json);
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{Result, bail};
+use std::str::FromStr;
+
+/// One of `count` slices of a test suite, written `<index>/<count>` with `index` starting at 1.
+///
+/// Tests are sorted by name and dealt out in turn, so every runner given the same tests picks
+/// the same slice and the slices are as even as possible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestShard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl TestShard {
+    /// The items of this shard, in their original order.
+    pub fn select<T>(&self, items: Vec<T>, name: impl Fn(&T) -> String) -> Vec<T> {
+        let mut names: Vec<_> = items.iter().map(&name).collect();
+        names.sort();
+
+        items
+            .into_iter()
+            .filter(|item| {
+                let position = names.binary_search(&name(item)).unwrap_or_default();
+                position % self.count == self.index - 1
+            })
+            .collect()
+    }
+}
+
+impl FromStr for TestShard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parsed = s
+            .split_once('/')
+            .and_then(|(index, count)| Some((index.parse().ok()?, count.parse().ok()?)));
+
+        match parsed {
+            Some((index, count)) if index >= 1 && index <= count => Ok(Self { index, count }),
+            _ => bail!("invalid shard `{s}`, expected <index>/<count> such as 1/3"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("test_{i:02}")).rev().collect()
+    }
+
+    #[test]
+    fn shards_cover_every_test_once() {
+        let tests = names(10);
+        for count in 1..=4 {
+            let mut selected: Vec<_> = (1..=count)
+                .flat_map(|index| TestShard { index, count }.select(tests.clone(), String::clone))
+                .collect();
+            selected.sort();
+
+            let mut expected = tests.clone();
+            expected.sort();
+            assert_eq!(selected, expected, "{count} shards");
+        }
+    }
+
+    #[test]
+    fn shards_are_even_and_keep_the_original_order() {
+        let shard = TestShard { index: 2, count: 3 };
+        assert_eq!(
+            shard.select(names(7), String::clone),
+            ["test_04", "test_01"]
+        );
+        assert_eq!(
+            shard.select(vec!["only".to_string()], String::clone),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "2/3".parse::<TestShard>().unwrap(),
+            TestShard { index: 2, count: 3 }
+        );
+        for invalid in ["0/3", "4/3", "1", "a/b", "1/0"] {
+            assert!(invalid.parse::<TestShard>().is_err(), "{invalid}");
+        }
+    }
+}
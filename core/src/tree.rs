@@ -0,0 +1,340 @@
+use crate::{
+    DependencyKind, KelpieContext, Package, PackageId, ProjectId, ProjectKind, TREE_BRANCH,
+    TREE_CORNER, TREE_SPACE, TREE_VERTICAL,
+};
+use anyhow::{Result, anyhow, bail};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// How `kl tree` starts each line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TreePrefix {
+    None,
+    #[default]
+    Indent,
+    /// The depth of the package as a number, easier to parse than the indentation.
+    Depth,
+}
+
+impl FromStr for TreePrefix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(Self::None),
+            "indent" => Ok(Self::Indent),
+            "depth" => Ok(Self::Depth),
+            _ => bail!("unknown prefix `{s}`, expected `none`, `indent` or `depth`"),
+        }
+    }
+}
+
+/// Parses an edge kind of `kl tree -e`.
+pub fn parse_edge_kind(kind: &str) -> Result<DependencyKind> {
+    match kind {
+        "normal" => Ok(DependencyKind::Normal),
+        "dev" => Ok(DependencyKind::Development),
+        "build" => Ok(DependencyKind::Build),
+        _ => bail!("unknown edge kind `{kind}`, expected `normal`, `dev` or `build`"),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    /// Packages deeper than this aren't printed, the roots are at depth 0.
+    pub depth: Option<usize>,
+    pub prefix: TreePrefix,
+}
+
+/// A dependency of a package on another, or the reverse in an inverted tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyEdge {
+    pub package: PackageId,
+    pub kind: DependencyKind,
+    /// Features the dependent enables on the dependency.
+    pub features: Vec<String>,
+}
+
+/// The packages reachable from the members of a project, and the kind of each dependency
+/// between them. Dev-dependencies are only followed from members, like when building.
+#[derive(Debug, Clone)]
+pub struct PackageTree<'a> {
+    ctx: &'a KelpieContext,
+    members: Vec<PackageId>,
+    edges: HashMap<PackageId, Vec<DependencyEdge>>,
+}
+
+impl<'a> PackageTree<'a> {
+    pub fn new(
+        ctx: &'a KelpieContext,
+        project_id: ProjectId,
+        kinds: &[DependencyKind],
+    ) -> Result<Self> {
+        let project = ctx
+            .get_project(project_id)
+            .ok_or_else(|| anyhow!("Failed to get project"))?;
+        let (members, shared) = match &project.kind {
+            ProjectKind::Workspace(workspace) => {
+                (workspace.members.clone(), project.dependencies.as_slice())
+            }
+            ProjectKind::Package(package_id) => (vec![*package_id], [].as_slice()),
+        };
+
+        let mut tree = Self {
+            ctx,
+            members: members.clone(),
+            edges: HashMap::new(),
+        };
+        let mut pending = members.clone();
+        while let Some(package_id) = pending.pop() {
+            if tree.edges.contains_key(&package_id) {
+                continue;
+            }
+
+            let own = ctx
+                .find_project_by_package_id(package_id)
+                .and_then(|project_id| ctx.get_project(project_id))
+                .map(|project| project.dependencies.as_slice())
+                .unwrap_or_default();
+            let is_member = members.contains(&package_id);
+            let inherited = if is_member { shared } else { &[] };
+
+            let mut edges: Vec<DependencyEdge> = vec![];
+            for dep in inherited.iter().chain(own) {
+                if !kinds.contains(&dep.kind)
+                    || (dep.kind == DependencyKind::Development && !is_member)
+                    || edges
+                        .iter()
+                        .any(|edge| edge.package == dep.id && edge.kind == dep.kind)
+                {
+                    continue;
+                }
+                edges.push(DependencyEdge {
+                    package: dep.id,
+                    kind: dep.kind,
+                    features: dep.features.clone(),
+                });
+                pending.push(dep.id);
+            }
+            tree.edges.insert(package_id, edges);
+        }
+
+        Ok(tree)
+    }
+
+    pub fn members(&self) -> &[PackageId] {
+        &self.members
+    }
+
+    /// Every package in the tree, in no particular order.
+    pub fn packages(&self) -> impl Iterator<Item = PackageId> + '_ {
+        self.edges.keys().copied()
+    }
+
+    pub fn dependencies(&self, package_id: PackageId) -> &[DependencyEdge] {
+        self.edges
+            .get(&package_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Points every edge the other way, so the tree shows who depends on a package.
+    pub fn invert(&self) -> Self {
+        let mut edges: HashMap<PackageId, Vec<DependencyEdge>> = self
+            .edges
+            .keys()
+            .map(|&package_id| (package_id, vec![]))
+            .collect();
+        for (&dependent, dependencies) in &self.edges {
+            for edge in dependencies {
+                edges.entry(edge.package).or_default().push(DependencyEdge {
+                    package: dependent,
+                    ..edge.clone()
+                });
+            }
+        }
+
+        Self {
+            ctx: self.ctx,
+            members: self.members.clone(),
+            edges,
+        }
+    }
+
+    /// Every package in the tree whose name matches `matches`, sorted by name and version.
+    pub fn find(&self, mut matches: impl FnMut(&Package) -> bool) -> Vec<PackageId> {
+        let mut found: Vec<&Package> = self
+            .edges
+            .keys()
+            .filter_map(|&package_id| self.ctx.get_package(package_id))
+            .filter(|package| matches(package))
+            .collect();
+        found.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        found.into_iter().map(|package| package.id).collect()
+    }
+
+    /// Packages present in more than one version, by name.
+    pub fn duplicates(&self) -> BTreeMap<String, Vec<PackageId>> {
+        let mut by_name: BTreeMap<String, Vec<&Package>> = BTreeMap::new();
+        for package in self.edges.keys().filter_map(|&id| self.ctx.get_package(id)) {
+            by_name
+                .entry(package.name.clone())
+                .or_default()
+                .push(package);
+        }
+
+        by_name
+            .into_iter()
+            .filter_map(|(name, mut packages)| {
+                packages.sort_by(|a, b| a.version.cmp(&b.version));
+                let versions: HashSet<&str> = packages
+                    .iter()
+                    .map(|package| package.version.as_str())
+                    .collect();
+                (versions.len() > 1)
+                    .then(|| (name, packages.iter().map(|package| package.id).collect()))
+            })
+            .collect()
+    }
+
+    /// Renders the tree below each of `roots`, separated by empty lines. A package that was
+    /// already expanded is marked with `(*)` instead of being printed again.
+    pub fn render(&self, roots: &[PackageId], options: &TreeOptions) -> String {
+        let mut out = String::new();
+        for (index, &root) in roots.iter().enumerate() {
+            if index > 0 {
+                out.push('\n');
+            }
+            let mut expanded = HashSet::new();
+            self.render_node(&mut out, root, &mut vec![], &mut expanded, options);
+        }
+        out
+    }
+
+    fn render_node(
+        &self,
+        out: &mut String,
+        package_id: PackageId,
+        parents: &mut Vec<bool>,
+        expanded: &mut HashSet<PackageId>,
+        options: &TreeOptions,
+    ) {
+        let Some(package) = self.ctx.get_package(package_id) else {
+            return;
+        };
+
+        match options.prefix {
+            TreePrefix::None => {}
+            TreePrefix::Depth => {
+                let _ = write!(out, "{}", parents.len());
+            }
+            TreePrefix::Indent => {
+                if let Some((&last, rest)) = parents.split_last() {
+                    for &has_sibling in rest {
+                        out.push_str(if has_sibling {
+                            TREE_VERTICAL
+                        } else {
+                            TREE_SPACE
+                        });
+                    }
+                    out.push_str(if last { TREE_BRANCH } else { TREE_CORNER });
+                }
+            }
+        }
+        let _ = write!(out, "{} v{}", package.name, package.version);
+
+        let edges = &self.edges[&package_id];
+        let at_depth_limit = options.depth.is_some_and(|depth| parents.len() >= depth);
+        if !edges.is_empty() && !at_depth_limit && !expanded.insert(package_id) {
+            out.push_str(" (*)\n");
+            return;
+        }
+        out.push('\n');
+
+        if at_depth_limit {
+            return;
+        }
+
+        for kind in DependencyKind::ALL {
+            let mut children: Vec<&Package> = edges
+                .iter()
+                .filter(|edge| edge.kind == kind)
+                .filter_map(|edge| self.ctx.get_package(edge.package))
+                .collect();
+            if children.is_empty() {
+                continue;
+            }
+            children.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+            if kind != DependencyKind::Normal && options.prefix == TreePrefix::Indent {
+                for &has_sibling in parents.iter() {
+                    out.push_str(if has_sibling {
+                        TREE_VERTICAL
+                    } else {
+                        TREE_SPACE
+                    });
+                }
+                let _ = writeln!(out, "[{}]", kind.table_name());
+            }
+
+            let count = children.len();
+            for (index, child) in children.into_iter().enumerate() {
+                parents.push(index < count - 1);
+                self.render_node(out, child.id, parents, expanded, options);
+                parents.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl<'a> PackageTree<'a> {
+    /// A tree of `members` with the given `(dependent, edge)` pairs, for graphs that manifests
+    /// can't describe.
+    pub(crate) fn from_edges(
+        ctx: &'a KelpieContext,
+        members: Vec<PackageId>,
+        edges: Vec<(PackageId, DependencyEdge)>,
+    ) -> Self {
+        let mut tree = Self {
+            ctx,
+            members: members.clone(),
+            edges: members.into_iter().map(|id| (id, vec![])).collect(),
+        };
+        for (dependent, edge) in edges {
+            tree.edges.entry(edge.package).or_default();
+            tree.edges.entry(dependent).or_default().push(edge);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn cycles_end_at_the_first_repeat() {
+        let fixture = Fixture::libraries(&["a", "b"]);
+        let (ctx, _) = fixture.load();
+        let a = ctx.get_package_by_name("a").unwrap().id;
+        let b = ctx.get_package_by_name("b").unwrap().id;
+
+        // manifests that depend on each other don't load, so the cycle is made by hand
+        let edge = |package| DependencyEdge {
+            package,
+            kind: DependencyKind::Normal,
+            features: vec![],
+        };
+        let tree = PackageTree::from_edges(&ctx, vec![a, b], vec![(a, edge(b)), (b, edge(a))]);
+
+        let expected = "a v0.1.0\n└── b v0.1.0\n    └── a v0.1.0 (*)\n";
+        assert_eq!(tree.render(&[a], &TreeOptions::default()), expected);
+        assert_eq!(
+            tree.invert().render(&[a], &TreeOptions::default()),
+            expected
+        );
+    }
+}
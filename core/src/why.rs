@@ -0,0 +1,196 @@
+use crate::{DependencyEdge, DependencyKind, KelpieContext, PackageId, PackageTree};
+use std::collections::{HashMap, VecDeque};
+
+/// A chain of dependencies from a package to another, see [`shortest_paths`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyPath {
+    pub from: PackageId,
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl DependencyPath {
+    /// `app v0.1.0 -> lib v0.2.0 [build, features: fast]`, every package followed by why the
+    /// previous one depends on it.
+    pub fn display(&self, ctx: &KelpieContext) -> String {
+        let mut out = package_label(ctx, self.from);
+        for edge in &self.edges {
+            out.push_str(" -> ");
+            out.push_str(&package_label(ctx, edge.package));
+
+            let mut reasons = vec![];
+            match edge.kind {
+                DependencyKind::Normal => {}
+                DependencyKind::Development => reasons.push("dev".to_string()),
+                DependencyKind::Build => reasons.push("build".to_string()),
+            }
+            if !edge.features.is_empty() {
+                reasons.push(format!("features: {}", edge.features.join(", ")));
+            }
+            if !reasons.is_empty() {
+                out.push_str(&format!(" [{}]", reasons.join(", ")));
+            }
+        }
+        out
+    }
+}
+
+/// Every shortest path from each workspace member to `target`. Paths from different members
+/// can have different lengths, and a member has several paths when they are equally short.
+pub fn shortest_paths(tree: &PackageTree, target: PackageId) -> Vec<DependencyPath> {
+    let mut paths = vec![];
+    for &member in tree.members() {
+        if member == target {
+            continue;
+        }
+
+        // breadth-first, remembering every edge that reaches a package at its shortest distance
+        let mut distance = HashMap::from([(member, 0)]);
+        let mut reached_by: HashMap<PackageId, Vec<(PackageId, &DependencyEdge)>> = HashMap::new();
+        let mut queue = VecDeque::from([member]);
+        while let Some(package) = queue.pop_front() {
+            let next = distance[&package] + 1;
+            if distance.get(&target).is_some_and(|&found| next > found) {
+                break;
+            }
+
+            for edge in tree.dependencies(package) {
+                match distance.get(&edge.package) {
+                    Some(&known) if known < next => continue,
+                    Some(_) => {}
+                    None => {
+                        distance.insert(edge.package, next);
+                        queue.push_back(edge.package);
+                    }
+                }
+                reached_by
+                    .entry(edge.package)
+                    .or_default()
+                    .push((package, edge));
+            }
+        }
+
+        let mut stack = vec![(target, vec![])];
+        while let Some((package, mut edges)) = stack.pop() {
+            if package == member {
+                edges.reverse();
+                paths.push(DependencyPath {
+                    from: member,
+                    edges,
+                });
+                continue;
+            }
+            for &(dependent, edge) in reached_by.get(&package).into_iter().flatten() {
+                let mut edges = edges.clone();
+                edges.push(edge.clone());
+                stack.push((dependent, edges));
+            }
+        }
+    }
+    paths
+}
+
+fn package_label(ctx: &KelpieContext, package_id: PackageId) -> String {
+    match ctx.get_package(package_id) {
+        Some(package) => format!("{} v{}", package.name, package.version),
+        None => String::from("?"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// The paths from `app` to `target` through `edges`, each given as `dependent dependency`.
+    fn paths(edges: &[&str], target: &str) -> Vec<String> {
+        let fixture = Fixture::libraries(&["app", "a", "b", "c", "target"]);
+        let (ctx, _) = fixture.load();
+        let id = |name| ctx.get_package_by_name(name).unwrap().id;
+
+        let edges = edges
+            .iter()
+            .map(|edge| {
+                let (dependent, dependency) = edge.split_once(' ').unwrap();
+                let edge = DependencyEdge {
+                    package: id(dependency),
+                    kind: DependencyKind::Normal,
+                    features: vec![],
+                };
+                (id(dependent), edge)
+            })
+            .collect();
+        let tree = PackageTree::from_edges(&ctx, vec![id("app")], edges);
+
+        let mut paths: Vec<String> = shortest_paths(&tree, id(target))
+            .iter()
+            .map(|path| path.display(&ctx).replace(" v0.1.0", ""))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn equally_short_paths_are_all_kept() {
+        assert_eq!(
+            paths(&["app a", "app b", "a target", "b target"], "target"),
+            ["app -> a -> target", "app -> b -> target"]
+        );
+    }
+
+    #[test]
+    fn longer_paths_are_dropped() {
+        assert_eq!(
+            paths(&["app a", "app b", "a target", "b c", "c target"], "target"),
+            ["app -> a -> target"]
+        );
+    }
+
+    #[test]
+    fn diamonds_give_a_path_per_side() {
+        assert_eq!(
+            paths(&["app a", "app b", "a c", "b c", "c target"], "target"),
+            ["app -> a -> c -> target", "app -> b -> c -> target"]
+        );
+    }
+
+    #[test]
+    fn cycles_are_not_followed() {
+        assert_eq!(
+            paths(&["app a", "a b", "b a", "b app", "b target"], "target"),
+            ["app -> a -> b -> target"]
+        );
+    }
+
+    #[test]
+    fn unreachable_and_member_targets_have_no_path() {
+        assert!(paths(&["app a"], "target").is_empty());
+        assert!(paths(&["app a", "a app"], "app").is_empty());
+    }
+
+    #[test]
+    fn display_names_the_kind_and_features() {
+        let fixture = Fixture::libraries(&["app", "a", "b"]);
+        let (ctx, _) = fixture.load();
+        let id = |name| ctx.get_package_by_name(name).unwrap().id;
+
+        let path = DependencyPath {
+            from: id("app"),
+            edges: vec![
+                DependencyEdge {
+                    package: id("a"),
+                    kind: DependencyKind::Build,
+                    features: vec!["fast".to_string(), "small".to_string()],
+                },
+                DependencyEdge {
+                    package: id("b"),
+                    kind: DependencyKind::Development,
+                    features: vec![],
+                },
+            ],
+        };
+        assert_eq!(
+            path.display(&ctx),
+            "app v0.1.0 -> a v0.1.0 [build, features: fast, small] -> b v0.1.0 [dev]"
+        );
+    }
+}
@@ -10,41 +10,116 @@ use zirael_utils::prelude::canonicalize_with_strip;
 
 static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+/// Stands for the directory of the project in environment values and the expected output.
+const ROOT: &str = "{root}";
+
 #[derive(Debug, Clone)]
 pub struct Project {
     files: HashMap<String, String>,
+    executables: Vec<String>,
+    git_repositories: Vec<(String, String)>,
     args: Vec<String>,
+    envs: Vec<(String, String)>,
     expected_output: Option<String>,
+    expected_stdout: Option<String>,
+    expected_files: Vec<(String, String)>,
+    expected_code: Option<i32>,
     command: Option<String>,
+    dir: String,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Project {
     pub fn new() -> Self {
         Self {
             files: HashMap::new(),
+            executables: Vec::new(),
+            git_repositories: Vec::new(),
             args: Vec::new(),
+            envs: Vec::new(),
             expected_output: None,
+            expected_stdout: None,
+            expected_files: Vec::new(),
+            expected_code: None,
             command: None,
+            dir: String::new(),
         }
     }
 
     pub fn file(mut self, path: &str, content: &str) -> Self {
-        self.files.insert(path.to_string(), content.to_string());
+        self.files.insert(path.to_owned(), content.to_owned());
         self
     }
 
+    /// A file that can be run, like a script with a shebang.
+    pub fn executable(mut self, path: &str, content: &str) -> Self {
+        self.executables.push(path.to_owned());
+        self.file(path, content)
+    }
+
+    /// Commits the files in the directory `path` to a git repository tagged `tag`, to be used
+    /// as a git dependency.
+    pub fn git_repository(mut self, path: &str, tag: &str) -> Self {
+        self.git_repositories
+            .push((path.to_owned(), tag.to_owned()));
+        self
+    }
+
+    /// Adds an argument of the command. `{root}` is replaced with the directory of the
+    /// project.
     pub fn arg(mut self, arg: &str) -> Self {
-        self.args.push(arg.to_string());
+        self.args.push(arg.to_owned());
+        self
+    }
+
+    /// Sets an environment variable of the command. `{root}` in the value is replaced with the
+    /// directory of the project.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.envs.push((key.to_owned(), value.to_owned()));
         self
     }
 
+    /// Checks what the command logged to stderr, with the directory of the project written as
+    /// `{root}`.
     pub fn expected_output(mut self, output: &str) -> Self {
-        self.expected_output = Some(output.to_string());
+        self.expected_output = Some(output.to_owned());
+        self
+    }
+
+    /// Checks what the command printed to stdout, with the directory of the project written
+    /// as `{root}`.
+    pub fn expected_stdout(mut self, output: &str) -> Self {
+        self.expected_stdout = Some(output.to_owned());
+        self
+    }
+
+    /// Checks the content of a file of the project once the command has run, with the
+    /// directory of the project written as `{root}`.
+    pub fn expected_file(mut self, path: &str, content: &str) -> Self {
+        self.expected_files
+            .push((path.to_owned(), content.to_owned()));
+        self
+    }
+
+    /// Checks the exit code of the command instead of expecting it to succeed.
+    pub fn expected_code(mut self, code: i32) -> Self {
+        self.expected_code = Some(code);
         self
     }
 
     pub fn command(mut self, command: &str) -> Self {
-        self.command = Some(command.to_string());
+        self.command = Some(command.to_owned());
+        self
+    }
+
+    /// Runs the command from a directory of the project rather than its root.
+    pub fn dir(mut self, path: &str) -> Self {
+        self.dir = path.to_owned();
         self
     }
 
@@ -53,6 +128,12 @@ impl Project {
         self
     }
 
+    /// Writes the project into a temporary directory and runs the command in it.
+    ///
+    /// # Errors
+    ///
+    /// When the CLI can't be built or run, or when its output, exit code or files aren't the
+    /// expected ones.
     pub fn run(&self) -> Result<()> {
         let exe_name = if cfg!(windows) { "cli.exe" } else { "cli" };
         let exe_path = Path::new("../target").join("debug").join(exe_name);
@@ -77,7 +158,7 @@ impl Project {
         let exe_path = canonicalize_with_strip(&exe_path)?;
 
         let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
-        let temp_name = format!("kelpie-test-{}", counter);
+        let temp_name = format!("kelpie-test-{counter}");
         let temp = TempDir::new(&temp_name)?;
         let temp_path = temp.path();
 
@@ -89,24 +170,45 @@ impl Project {
             if let Some(parent) = full_path.parent() {
                 fs_err::create_dir_all(parent)?;
             }
-            fs_err::write(full_path, content)?;
+            fs_err::write(&full_path, content)?;
+            if self.executables.contains(path) {
+                make_executable(&full_path)?;
+            }
+        }
+
+        for (path, tag) in &self.git_repositories {
+            init_git_repository(&temp_path.join(path), tag)?;
         }
 
         set_current_dir(temp_path)?;
 
         let mut cmd = Command::new(&exe_path);
-        cmd.arg(self.command.as_ref().unwrap_or(&"run".to_string()))
-            .args(&self.args)
+        cmd.arg(self.command.as_deref().unwrap_or("run"))
             .arg("--test-logger")
-            .current_dir(temp_path)
+            .args(
+                self.args
+                    .iter()
+                    .map(|arg| arg.replace(ROOT, &temp_path.to_string_lossy())),
+            )
+            .current_dir(temp_path.join(&self.dir))
             .stdin(Stdio::inherit())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
+        for (key, value) in &self.envs {
+            cmd.env(key, value.replace(ROOT, &temp_path.to_string_lossy()));
+        }
 
         let output = cmd.output()?;
 
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        println!("{}", stderr);
+        let root = canonicalize_with_strip(temp_path)?;
+        let with_root = |output: &[u8]| {
+            String::from_utf8_lossy(output)
+                .replace(&*root.to_string_lossy(), ROOT)
+                .replace(&*temp_path.to_string_lossy(), ROOT)
+        };
+        let stderr = with_root(&output.stderr);
+        let stdout = with_root(&output.stdout);
+        println!("{stderr}");
 
         if let Some(expected_output) = &self.expected_output {
             let actual_output = stderr.trim();
@@ -114,12 +216,10 @@ impl Project {
 
             if actual_output != expected_output {
                 return Err(anyhow::anyhow!(
-                    "Output mismatch!\nExpected:\n{}\nActual:\n{}",
-                    expected_output,
-                    actual_output
+                    "Output mismatch!\nExpected:\n{expected_output}\nActual:\n{actual_output}"
                 ));
             }
-        } else if !output.status.success() {
+        } else if self.expected_code.is_none() && !output.status.success() {
             return Err(anyhow::anyhow!(
                 "Command failed with exit code {:?}\nOutput:\n{}",
                 output.status.code(),
@@ -127,10 +227,97 @@ impl Project {
             ));
         }
 
+        if let Some(code) = self.expected_code
+            && output.status.code() != Some(code)
+        {
+            return Err(anyhow::anyhow!(
+                "Expected exit code {code}, got {:?}",
+                output.status.code()
+            ));
+        }
+        if let Some(expected_stdout) = &self.expected_stdout {
+            compare("stdout", expected_stdout, &stdout)?;
+        }
+        for (path, expected) in &self.expected_files {
+            let actual = with_root(fs_err::read_to_string(temp_path.join(path))?.as_bytes());
+            compare(path, expected, &actual)?;
+        }
+
         Ok(())
     }
 }
 
+/// Compares ignoring common indentation, as fixtures are written inline in the tests.
+fn compare(what: &str, expected: &str, actual: &str) -> Result<()> {
+    let expected = unindent(expected);
+    let actual = unindent(actual);
+    if actual != expected {
+        return Err(anyhow::anyhow!(
+            "{what} mismatch!\nExpected:\n{expected}\nActual:\n{actual}"
+        ));
+    }
+    Ok(())
+}
+
+fn unindent(text: &str) -> String {
+    let text = text.trim_matches('\n');
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_owned()
+}
+
+/// `git init` in `dir`, with everything in it committed and tagged `tag`.
+fn init_git_repository(dir: &Path, tag: &str) -> Result<()> {
+    let git = |args: &[&str]| -> Result<()> {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=kelpie",
+                "-c",
+                "user.email=kelpie@localhost",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(anyhow::anyhow!(
+                "git {} failed in {}",
+                args.join(" "),
+                dir.display()
+            ));
+        }
+        Ok(())
+    };
+
+    git(&["init", "--quiet"])?;
+    git(&["add", "."])?;
+    git(&["commit", "--quiet", "-m", "initial"])?;
+    git(&["tag", tag])
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt as _;
+    fs_err::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
 struct Guard(PathBuf);
 
 impl Drop for Guard {
@@ -0,0 +1,285 @@
+#[cfg(test)]
+mod add_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    fn package_with_foo() -> Project {
+        Project::new()
+            .file(
+                "config.toml",
+                "[package]\nname = \"test\"\nversion = \"0.1.0\"\n",
+            )
+            .file("src/lib.zr", "")
+            .file(
+                "foo/config.toml",
+                "[package]\nname = \"foo\"\nversion = \"0.3.0\"\n",
+            )
+            .file("foo/src/lib.zr", "")
+    }
+
+    #[test]
+    fn add_path_dependency_uses_its_version() -> Result<()> {
+        package_with_foo()
+            .command("add")
+            .arg("--path")
+            .arg("foo")
+            .expected_output("info Added foo v0.3.0 to [dependencies]")
+            .expected_file(
+                "config.toml",
+                r#"
+                [package]
+                name = "test"
+                version = "0.1.0"
+
+                [dependencies]
+                foo = { version = "0.3.0", path = "foo" }
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn add_dev_dependency_with_features() -> Result<()> {
+        package_with_foo()
+            .command("add")
+            .arg("--path")
+            .arg("foo")
+            .arg("--dev")
+            .arg("-F")
+            .arg("fast,small")
+            .arg("-F")
+            .arg("color")
+            .expected_output(
+                "info Added foo v0.3.0 to [dev-dependencies]\ninfo Features: fast, small, color",
+            )
+            .expected_file(
+                "config.toml",
+                r#"
+                [package]
+                name = "test"
+                version = "0.1.0"
+
+                [dev-dependencies]
+                foo = { version = "0.3.0", path = "foo", features = ["fast", "small", "color"] }
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn add_build_dependency_with_version_requirement() -> Result<()> {
+        package_with_foo()
+            .command("add")
+            .arg("foo@^0.3")
+            .arg("--path")
+            .arg("foo")
+            .arg("--build")
+            .expected_output("info Added foo v^0.3 to [build-dependencies]")
+            .expected_file(
+                "config.toml",
+                r#"
+                [package]
+                name = "test"
+                version = "0.1.0"
+
+                [build-dependencies]
+                foo = { version = "^0.3", path = "foo" }
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn add_git_dependency_at_tag() -> Result<()> {
+        package_with_foo()
+            .git_repository("foo", "v0.3.0")
+            .env("KELPIE_HOME", "{root}/home")
+            .command("add")
+            .arg("foo")
+            .arg("--git")
+            .arg("file://{root}/foo")
+            .arg("--tag")
+            .arg("v0.3.0")
+            .expected_output("info Added foo v0.3.0 to [dependencies]")
+            .expected_file(
+                "config.toml",
+                r#"
+                [package]
+                name = "test"
+                version = "0.1.0"
+
+                [dependencies]
+                foo = { version = "0.3.0", git = "file://{root}/foo", tag = "v0.3.0" }
+                "#,
+            )
+            .run()
+    }
+
+    fn workspace_with_foo() -> Project {
+        Project::new()
+            .file("config.toml", "[workspace]\nmembers = [\"app\", \"cli\"]\n")
+            .file(
+                "app/config.toml",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+            )
+            .file("app/src/lib.zr", "")
+            .file(
+                "cli/config.toml",
+                "[package]\nname = \"cli\"\nversion = \"0.1.0\"\n",
+            )
+            .file("cli/src/lib.zr", "")
+            .file(
+                "foo/config.toml",
+                "[package]\nname = \"foo\"\nversion = \"0.3.0\"\n",
+            )
+            .file("foo/src/lib.zr", "")
+    }
+
+    #[test]
+    fn add_to_selected_package() -> Result<()> {
+        workspace_with_foo()
+            .command("add")
+            .arg("--path")
+            .arg("foo")
+            .arg("-p")
+            .arg("cli")
+            .expected_output("info Added foo v0.3.0 to [dependencies]")
+            .expected_file(
+                "cli/config.toml",
+                r#"
+                [package]
+                name = "cli"
+                version = "0.1.0"
+
+                [dependencies]
+                foo = { version = "0.3.0", path = "../foo" }
+                "#,
+            )
+            .expected_file(
+                "app/config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn add_to_workspace_references_it_from_the_package() -> Result<()> {
+        workspace_with_foo()
+            .command("add")
+            .arg("--path")
+            .arg("foo")
+            .arg("-p")
+            .arg("app")
+            .arg("--to-workspace")
+            .arg("-F")
+            .arg("fast")
+            .expected_output("info Added foo v0.3.0 to [dependencies]\ninfo Features: fast")
+            .expected_file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["app", "cli"]
+
+                [workspace.dependencies]
+                foo = { version = "0.3.0", path = "foo" }
+                "#,
+            )
+            .expected_file(
+                "app/config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [dependencies]
+                foo = { features = ["fast"], workspace = true }
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn add_unresolvable_dependency_is_error() -> Result<()> {
+        package_with_foo()
+            .command("add")
+            .arg("bar@^1.2")
+            .expected_output("error couldn't resolve dependency: bar")
+            .run()
+    }
+
+    #[test]
+    fn add_requires_package_in_workspace() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                "[workspace]\nmembers = [\"pkg1\"]\n",
+            )
+            .file(
+                "pkg1/config.toml",
+                "[package]\nname = \"pkg1\"\nversion = \"0.1.0\"\n",
+            )
+            .file("pkg1/src/lib.zr", "")
+            .command("add")
+            .arg("foo@1")
+            .expected_output("error Cannot add a dependency in a workspace without specifying a package (use -p <package-name> or --to-workspace)")
+            .run()
+    }
+
+    #[test]
+    fn remove_missing_dependency_is_error() -> Result<()> {
+        package_with_foo()
+            .command("remove")
+            .arg("foo")
+            .arg("--dev")
+            .expected_output("error dependency foo not found in [dev-dependencies]")
+            .run()
+    }
+
+    #[test]
+    fn remove_dependency_and_its_empty_table() -> Result<()> {
+        package_with_foo()
+            .file(
+                "config.toml",
+                "[package]\nname = \"test\"\nversion = \"0.1.0\"\n\n[dependencies]\nfoo = { version = \"0.3.0\", path = \"foo\" }\n\n[dev-dependencies]\nfoo = { version = \"0.3.0\", path = \"foo\" }\n",
+            )
+            .command("remove")
+            .arg("foo")
+            .expected_output("info Removed foo from [dependencies]")
+            .expected_file(
+                "config.toml",
+                r#"
+                [package]
+                name = "test"
+                version = "0.1.0"
+
+                [dev-dependencies]
+                foo = { version = "0.3.0", path = "foo" }
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn remove_last_workspace_dependency() -> Result<()> {
+        workspace_with_foo()
+            .file(
+                "config.toml",
+                "[workspace]\nmembers = [\"app\", \"cli\"]\n\n[workspace.dependencies]\nfoo = { version = \"0.3.0\", path = \"foo\" }\n",
+            )
+            .command("remove")
+            .arg("foo")
+            .expected_output("info Removed foo from [workspace.dependencies]")
+            .expected_file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["app", "cli"]
+                "#,
+            )
+            .run()
+    }
+}
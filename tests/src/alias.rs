@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod alias_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    fn package() -> Project {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+    }
+
+    #[test]
+    fn alias_expands_to_arguments() -> Result<()> {
+        package()
+            .file(
+                ".kelpie/config.toml",
+                r#"
+                [alias]
+                tb = ["test", "-p", "bar"]
+                "#,
+            )
+            .command("tb")
+            .expected_output("error Package 'bar' not found")
+            .run()
+    }
+
+    #[test]
+    fn alias_expands_to_alias() -> Result<()> {
+        package()
+            .file(
+                ".kelpie/config.toml",
+                r#"
+                [alias]
+                c = "chk -p bar"
+                chk = "check"
+                "#,
+            )
+            .command("c")
+            .expected_output("error Package 'bar' not found")
+            .run()
+    }
+
+    #[test]
+    fn recursive_alias_is_error() -> Result<()> {
+        package()
+            .file(
+                ".kelpie/config.toml",
+                r#"
+                [alias]
+                a = "b --release"
+                b = ["a"]
+                "#,
+            )
+            .command("a")
+            .expected_output("error alias `a` is recursive: a -> b -> a")
+            .run()
+    }
+
+    #[test]
+    fn alias_cannot_shadow_builtin_command() -> Result<()> {
+        package()
+            .file(
+                ".kelpie/config.toml",
+                r#"
+                [alias]
+                rm = "clean"
+                "#,
+            )
+            .command("tree")
+            .expected_output(
+                "error alias `rm` in {root}/.kelpie/config.toml shadows the built-in command of the same name",
+            )
+            .run()
+    }
+
+    #[test]
+    fn alias_cannot_be_empty() -> Result<()> {
+        package()
+            .command("tree")
+            .arg("--config")
+            .arg("alias.t=[]")
+            .expected_output("error alias `t` in --config is empty")
+            .run()
+    }
+}
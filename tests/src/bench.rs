@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod bench_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    fn package() -> Project {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+    }
+
+    #[test]
+    fn workspace_bench_needs_selection() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["foo"]
+                "#,
+            )
+            .file(
+                "foo/config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("foo/src/lib.zr", "")
+            .command("bench")
+            .expected_output(
+                "error Cannot bench in a workspace without specifying a package to bench (use -p <package-name> or --workspace)",
+            )
+            .run()
+    }
+
+    #[test]
+    fn baseline_has_to_exist() -> Result<()> {
+        package()
+            .command("bench")
+            .arg("--baseline")
+            .arg("main")
+            .expected_output(
+                "error baseline `main` not found, save one with `kl bench --save-baseline main`",
+            )
+            .run()
+    }
+
+    #[test]
+    fn saved_baseline_name_is_checked_first() -> Result<()> {
+        package()
+            .command("bench")
+            .arg("--save-baseline")
+            .arg("../main")
+            .expected_output("error invalid baseline name `../main`")
+            .run()
+    }
+}
@@ -0,0 +1,210 @@
+#[cfg(test)]
+mod build_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn binary_cannot_be_built_as_library() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .file("src/main.zr", "fn main() {}")
+            .command("build")
+            .arg("--static")
+            .expected_output("error package app is a binary and cannot be built as a library")
+            .run()
+    }
+
+    #[test]
+    fn binary_cannot_specify_lib_type() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+                type = "bin"
+                lib-type = ["static"]
+                "#,
+            )
+            .file("src/main.zr", "fn main() {}")
+            .command("build")
+            .expected_output("error package app is a binary and cannot specify a lib-type")
+            .run()
+    }
+
+    #[test]
+    fn workspace_build_needs_selection() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["net-http"]
+                "#,
+            )
+            .file(
+                "net-http/config.toml",
+                r#"
+                [package]
+                name = "net-http"
+                version = "0.1.0"
+                "#,
+            )
+            .file("net-http/src/lib.zr", "")
+            .command("build")
+            .expected_output(
+                "error Cannot build in a workspace without specifying a package to build (use -p <package-name> or --workspace)",
+            )
+            .run()
+    }
+
+    #[test]
+    fn package_spec_version_has_to_match() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["net-http"]
+                "#,
+            )
+            .file(
+                "net-http/config.toml",
+                r#"
+                [package]
+                name = "net-http"
+                version = "0.1.0"
+                "#,
+            )
+            .file("net-http/src/lib.zr", "")
+            .command("build")
+            .arg("-p")
+            .arg("net-*@0.2")
+            .expected_output("error Package 'net-*@0.2' not found in workspace")
+            .run()
+    }
+
+    /// Prints the package name and the arguments after the ones kelpie always passes.
+    const STUB_COMPILER: &str = r#"#!/bin/sh
+[ "$1" = "--version" ] && echo "zc 1.0" && exit 0
+name=$2
+shift 3
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --mode|--output|--lib|-d) shift 2 ;;
+        *) break ;;
+    esac
+done
+echo "$name: $*"
+"#;
+
+    #[cfg(unix)]
+    #[test]
+    fn zflags_and_trailing_args_reach_the_compiler() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [build]
+                zflags = ["-Zreplaced"]
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .executable("zc", STUB_COMPILER)
+            .env("KELPIE_TOOLCHAIN_COMPILER", "{root}/zc")
+            .env("KELPIE_ZFLAGS", "-Zfast  -Zsmall")
+            .command("build")
+            .arg("--")
+            .arg("--emit")
+            .arg("ir")
+            .expected_stdout("foo: -Zfast -Zsmall --emit ir")
+            .run()
+    }
+
+    #[cfg(unix)]
+    fn verbose_project(verbose: bool) -> Project {
+        let project = Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                type = "bin"
+
+                [dependencies]
+                bar = { version = "0.1.0", path = "bar" }
+                "#,
+            )
+            .file("src/main.zr", "fn main() {}")
+            .file(
+                "bar/config.toml",
+                r#"
+                [package]
+                name = "bar"
+                version = "0.1.0"
+                "#,
+            )
+            .file("bar/src/lib.zr", "")
+            .executable(
+                "zc",
+                "#!/bin/sh\n[ \"$1\" = \"--version\" ] && echo \"zc 1.0\"\nexit 0\n",
+            )
+            .env("KELPIE_TOOLCHAIN_COMPILER", "{root}/zc")
+            .command("build");
+        if verbose { project.arg("-v") } else { project }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_tree_is_printed_when_verbose() -> Result<()> {
+        verbose_project(true)
+            .expected_success()
+            .expected_stdout(
+                "
+                foo v0.1.0
+                ├── Dependencies:
+                │   └── bar v0.1.0
+                ",
+            )
+            .run()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_tree_is_printed_when_verbose_is_configured() -> Result<()> {
+        verbose_project(false)
+            .env("KELPIE_TERM_VERBOSE", "true")
+            .expected_success()
+            .expected_stdout(
+                "
+                foo v0.1.0
+                ├── Dependencies:
+                │   └── bar v0.1.0
+                ",
+            )
+            .run()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_tree_is_not_printed_by_default() -> Result<()> {
+        verbose_project(false)
+            .expected_success()
+            .expected_stdout("")
+            .run()
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod check_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn workspace_check_needs_selection() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["foo"]
+                "#,
+            )
+            .file(
+                "foo/config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("foo/src/lib.zr", "")
+            .command("check")
+            .expected_output(
+                "error Cannot check in a workspace without specifying a package to check (use -p <package-name> or --workspace)",
+            )
+            .run()
+    }
+
+    #[test]
+    fn check_rejects_unknown_package() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("check")
+            .arg("-p")
+            .arg("bar")
+            .expected_output("error Package 'bar' not found")
+            .run()
+    }
+}
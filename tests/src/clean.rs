@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod clean_tests {
+    use anyhow::Result;
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+    use test_lib::Project;
+
+    /// The dynamic library of `foo` as named on this platform.
+    fn library() -> String {
+        format!("target/release/{DLL_PREFIX}foo{DLL_SUFFIX}")
+    }
+
+    /// The dynamic library of the dependency `name`, named after its version too.
+    fn dependency_library(name: &str) -> String {
+        format!("target/release/deps/{DLL_PREFIX}{name}-0.1.0{DLL_SUFFIX}")
+    }
+
+    fn project() -> Project {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .file("target/debug/.fingerprint/foo-0.1.0.toml", "a")
+            .file("target/debug/.fingerprint/foo-bar-0.1.0.toml", "a")
+            .file("target/release/.fingerprint/foo-0.1.0.toml", "a")
+            .file(&library(), "abc")
+            .file(&dependency_library("foo"), "ab")
+            .file("target/release/deps/foo-0.1.0.zrm", "a")
+            .file(&dependency_library("foo-bar"), "a")
+    }
+
+    #[test]
+    fn dry_run_lists_profile() -> Result<()> {
+        project()
+            .command("clean")
+            .arg("--release")
+            .arg("--dry-run")
+            .expected_output(
+                "info Would remove target/release\ninfo Would remove 5 files, 8B total",
+            )
+            .run()
+    }
+
+    #[test]
+    fn dry_run_lists_package_files() -> Result<()> {
+        project()
+            .command("clean")
+            .arg("-p")
+            .arg("foo")
+            .arg("-n")
+            .expected_output(&format!(
+                "info Would remove target/debug/.fingerprint/foo-0.1.0.toml\n\
+                 info Would remove {}\n\
+                 info Would remove {}\n\
+                 info Would remove target/release/deps/foo-0.1.0.zrm\n\
+                 info Would remove target/release/.fingerprint/foo-0.1.0.toml\n\
+                 info Would remove 5 files, 8B total",
+                library(),
+                dependency_library("foo")
+            ))
+            .run()
+    }
+
+    #[test]
+    fn removes_target_dir() -> Result<()> {
+        project()
+            .command("clean")
+            .expected_output("info Removed 7 files, 10B total")
+            .run()
+    }
+}
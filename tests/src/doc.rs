@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod doc_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn clean_removes_package_docs() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .file("target/doc/foo/index.html", "abc")
+            .file("target/doc/index.html", "a")
+            .command("clean")
+            .arg("-p")
+            .arg("foo")
+            .arg("-n")
+            .expected_output(
+                "info Would remove target/doc/foo\ninfo Would remove 1 files, 3B total",
+            )
+            .run()
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(test)]
+mod graph_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    /// The members `app` and `net`, two versions of `log`, and a dev- and a build-dependency.
+    fn workspace() -> Project {
+        let mut project = Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["app", "net"]
+                "#,
+            )
+            .file(
+                "app/config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [dependencies]
+                net = { version = "0.1.0", path = "../net" }
+                log = { version = "0.1.0", path = "../log" }
+
+                [dev-dependencies]
+                check = { version = "0.1.0", path = "../check" }
+
+                [build-dependencies]
+                gen = { version = "0.1.0", path = "../gen" }
+                "#,
+            )
+            .file(
+                "net/config.toml",
+                r#"
+                [package]
+                name = "net"
+                version = "0.1.0"
+
+                [dependencies]
+                log = { version = "0.2.0", path = "../log2" }
+                "#,
+            );
+
+        for (dir, name, version) in [
+            ("log", "log", "0.1.0"),
+            ("log2", "log", "0.2.0"),
+            ("check", "check", "0.1.0"),
+            ("gen", "gen", "0.1.0"),
+        ] {
+            project = project.file(
+                &format!("{dir}/config.toml"),
+                &format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n"),
+            );
+        }
+        for dir in ["app", "net", "log", "log2", "check", "gen"] {
+            project = project.file(&format!("{dir}/src/lib.zr"), "");
+        }
+        project
+    }
+
+    #[test]
+    fn dot_clusters_members_and_styles_edges() -> Result<()> {
+        workspace()
+            .command("graph")
+            .expected_stdout(
+                r#"
+                digraph dependencies {
+                    rankdir=LR;
+                    node [shape=box];
+                    subgraph cluster_workspace {
+                        label="workspace";
+                        style=dashed;
+                        n0 [label="app v0.1.0"];
+                        n5 [label="net v0.1.0"];
+                    }
+                    n1 [label="check v0.1.0"];
+                    n2 [label="gen v0.1.0"];
+                    n3 [label="log v0.1.0"];
+                    n4 [label="log v0.2.0"];
+                    n0 -> n1 [style=dotted, label="dev"];
+                    n0 -> n2 [style=dashed, label="build"];
+                    n0 -> n3;
+                    n0 -> n5;
+                    n5 -> n4;
+                }
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn mermaid_highlights_duplicates() -> Result<()> {
+        workspace()
+            .command("graph")
+            .arg("--format")
+            .arg("mermaid")
+            .arg("--highlight-duplicates")
+            .expected_stdout(
+                r#"
+                flowchart LR
+                    subgraph workspace
+                        n0["app v0.1.0"]
+                        n5["net v0.1.0"]
+                    end
+                    n1["check v0.1.0"]
+                    n2["gen v0.1.0"]
+                    n3["log v0.1.0"]
+                    n4["log v0.2.0"]
+                    n0 -.->|dev| n1
+                    n0 ==>|build| n2
+                    n0 --> n3
+                    n0 --> n5
+                    n5 --> n4
+                    classDef duplicate fill:#ffcc99
+                    class n3,n4 duplicate
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn focus_keeps_dependencies_and_dependents() -> Result<()> {
+        workspace()
+            .command("graph")
+            .arg("--focus")
+            .arg("net")
+            .expected_stdout(
+                r#"
+                digraph dependencies {
+                    rankdir=LR;
+                    node [shape=box];
+                    subgraph cluster_workspace {
+                        label="workspace";
+                        style=dashed;
+                        n0 [label="app v0.1.0"];
+                        n2 [label="net v0.1.0"];
+                    }
+                    n1 [label="log v0.2.0"];
+                    n0 -> n2;
+                    n2 -> n1;
+                }
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn focus_and_highlight_duplicates() -> Result<()> {
+        workspace()
+            .command("graph")
+            .arg("--format")
+            .arg("mermaid")
+            .arg("--focus")
+            .arg("log@0.1.0")
+            .arg("--highlight-duplicates")
+            .expected_stdout(
+                r#"
+                flowchart LR
+                    subgraph workspace
+                        n0["app v0.1.0"]
+                    end
+                    n1["log v0.1.0"]
+                    n0 --> n1
+                    classDef duplicate fill:#ffcc99
+                    class n1 duplicate
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn focus_rejects_unknown_package() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("graph")
+            .arg("--focus")
+            .arg("bar")
+            .expected_output("error Package 'bar' not found in the dependency tree")
+            .run()
+    }
+
+    #[test]
+    fn rejects_unknown_format() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("graph")
+            .arg("--format")
+            .arg("svg")
+            .expected_output(
+                "error: invalid value 'svg' for '--format <format>'\n  [possible values: dot, mermaid]\n\nFor more information, try '--help'.",
+            )
+            .run()
+    }
+}
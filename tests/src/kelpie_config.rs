@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod kelpie_config_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    fn package() -> Project {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+    }
+
+    #[test]
+    fn config_override_needs_key_and_value() -> Result<()> {
+        package()
+            .command("tree")
+            .arg("--config")
+            .arg("build.jobs")
+            .expected_output("error invalid --config `build.jobs`, expected KEY=VALUE")
+            .run()
+    }
+
+    #[test]
+    fn config_override_sets_alias() -> Result<()> {
+        package()
+            .command("tree")
+            .arg("--config")
+            .arg("alias.rm=clean")
+            .expected_output(
+                "error alias `rm` in --config shadows the built-in command of the same name",
+            )
+            .run()
+    }
+
+    #[test]
+    fn config_value_of_wrong_type_is_error() -> Result<()> {
+        package()
+            .file(
+                ".kelpie/config.toml",
+                r#"
+                [build]
+                jobs = "many"
+                "#,
+            )
+            .command("tree")
+            .expected_output(
+                "error invalid kelpie config: invalid type: string \"many\", expected usize\nin `build.jobs`",
+            )
+            .run()
+    }
+
+    /// Stub compilers printing the name of the place configuring them, the first `count` of
+    /// the user configuration, `.kelpie` above the package, `.kelpie` of the package, the
+    /// environment and `--config` being set. The command runs from the package.
+    #[cfg(unix)]
+    fn layers(count: usize) -> Project {
+        let mut project = Project::new()
+            .file(
+                "app/config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+                "#,
+            )
+            .file("app/src/lib.zr", "")
+            .env("KELPIE_HOME", "{root}/home")
+            .dir("app")
+            .command("build");
+        for name in ["home", "above", "package", "env", "config"] {
+            project = project.executable(
+                &format!("stubs/{name}"),
+                &format!(
+                    "#!/bin/sh\n[ \"$1\" = \"--version\" ] && echo \"zc 1.0\" && exit 0\necho {name}\n"
+                ),
+            );
+        }
+
+        let settings = [
+            ("home/config.toml", "../stubs/home"),
+            (".kelpie/config.toml", "stubs/above"),
+            ("app/.kelpie/config.toml", "../stubs/package"),
+        ];
+        for (file, compiler) in settings.into_iter().take(count) {
+            project = project.file(file, &format!("[toolchain]\ncompiler = \"{compiler}\"\n"));
+        }
+        if count > 3 {
+            project = project.env("KELPIE_TOOLCHAIN_COMPILER", "{root}/stubs/env");
+        }
+        if count > 4 {
+            project = project
+                .arg("--config")
+                .arg("toolchain.compiler=../stubs/config");
+        }
+        project
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn config_precedence() -> Result<()> {
+        for (count, expected) in [
+            (1, "home"),
+            (2, "above"),
+            (3, "package"),
+            (4, "env"),
+            (5, "config"),
+        ] {
+            layers(count)
+                .expected_success()
+                .expected_stdout(expected)
+                .run()?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,20 @@
+mod add;
+mod alias;
+mod bench;
+mod build;
+mod check;
+mod clean;
 mod config;
 mod dependencies;
+mod doc;
+mod graph;
+mod kelpie_config;
+mod metadata;
+mod new;
+mod plugins;
+mod run;
+mod test;
+mod tree;
+mod why;
 
 fn main() {}
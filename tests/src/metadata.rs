@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod metadata_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn only_format_version_1_is_supported() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("metadata")
+            .arg("--format-version")
+            .arg("2")
+            .expected_output(
+                "error: invalid value '2' for '--format-version <format-version>': 2 is not in 1..=1\n\nFor more information, try '--help'.",
+            )
+            .run()
+    }
+
+    #[test]
+    fn workspace_metadata() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["foo", "bar"]
+                "#,
+            )
+            .file(
+                "foo/config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                type = "bin"
+
+                [dependencies]
+                bar = { version = "0.1.0", path = "../bar", features = ["fast"] }
+                "#,
+            )
+            .file("foo/src/main.zr", "")
+            .file(
+                "bar/config.toml",
+                r#"
+                [package]
+                name = "bar"
+                version = "0.1.0"
+                description = "Fast bars"
+                "#,
+            )
+            .file("bar/src/lib.zr", "")
+            .file("bar/tests/api.zr", "")
+            .command("metadata")
+            .expected_success()
+            .expected_stdout(
+                r#"
+                {
+                  "version": 1,
+                  "workspace_root": "{root}",
+                  "target_directory": "{root}/target",
+                  "workspace_members": [
+                    "path+file://{root}/bar#bar@0.1.0",
+                    "path+file://{root}/foo#foo@0.1.0"
+                  ],
+                  "packages": [
+                    {
+                      "id": "path+file://{root}/bar#bar@0.1.0",
+                      "name": "bar",
+                      "version": "0.1.0",
+                      "author": null,
+                      "description": "Fast bars",
+                      "license": null,
+                      "repository": null,
+                      "homepage": null,
+                      "keywords": null,
+                      "type": "lib",
+                      "lib_types": [
+                        "dynamic"
+                      ],
+                      "root": "{root}/bar",
+                      "entrypoint": "src/lib.zr",
+                      "source": null,
+                      "manifest_path": "{root}/bar/config.toml",
+                      "features": [
+                        "fast"
+                      ],
+                      "targets": [
+                        {
+                          "name": "bar",
+                          "kind": "lib",
+                          "src_path": "{root}/bar/src/lib.zr"
+                        },
+                        {
+                          "name": "api",
+                          "kind": "test",
+                          "src_path": "{root}/bar/tests/api.zr"
+                        }
+                      ],
+                      "dependencies": []
+                    },
+                    {
+                      "id": "path+file://{root}/foo#foo@0.1.0",
+                      "name": "foo",
+                      "version": "0.1.0",
+                      "author": null,
+                      "description": null,
+                      "license": null,
+                      "repository": null,
+                      "homepage": null,
+                      "keywords": null,
+                      "type": "bin",
+                      "lib_types": [],
+                      "root": "{root}/foo",
+                      "entrypoint": "src/main.zr",
+                      "source": null,
+                      "manifest_path": "{root}/foo/config.toml",
+                      "features": [],
+                      "targets": [
+                        {
+                          "name": "foo",
+                          "kind": "bin",
+                          "src_path": "{root}/foo/src/main.zr"
+                        }
+                      ],
+                      "dependencies": [
+                        {
+                          "name": "bar",
+                          "req": "0.1.0",
+                          "kind": "normal",
+                          "features": [
+                            "fast"
+                          ]
+                        }
+                      ]
+                    }
+                  ],
+                  "resolve": {
+                    "root": null,
+                    "nodes": [
+                      {
+                        "id": "path+file://{root}/bar#bar@0.1.0",
+                        "dependencies": []
+                      },
+                      {
+                        "id": "path+file://{root}/foo#foo@0.1.0",
+                        "dependencies": [
+                          {
+                            "pkg": "path+file://{root}/bar#bar@0.1.0",
+                            "kind": "normal",
+                            "features": [
+                              "fast"
+                            ]
+                          }
+                        ]
+                      }
+                    ]
+                  }
+                }
+                "#,
+            )
+            .run()
+    }
+}
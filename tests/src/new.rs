@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod new_tests {
+    use anyhow::Result;
+    use kelpie_core::builtin_template_values;
+    use test_lib::Project;
+
+    #[test]
+    fn new_creates_library_by_default() -> Result<()> {
+        Project::new()
+            .command("new")
+            .arg("foo")
+            .expected_output("info Created library `foo` package")
+            .run()
+    }
+
+    #[test]
+    fn new_adds_package_to_workspace_members() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = []
+                "#,
+            )
+            .command("new")
+            .arg("app")
+            .arg("--bin")
+            .expected_output(
+                "info Created binary (application) `app` package\ninfo Added `app` to the workspace members",
+            )
+            .run()
+    }
+
+    #[test]
+    fn new_skips_members_covered_by_glob() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["packages/*"]
+                "#,
+            )
+            .command("new")
+            .arg("packages/foo")
+            .expected_output("info Created library `foo` package")
+            .run()
+    }
+
+    #[test]
+    fn new_rejects_invalid_names() -> Result<()> {
+        Project::new()
+            .command("new")
+            .arg("1foo")
+            .expected_output("error package name `1foo` cannot start with a digit")
+            .run()
+    }
+
+    #[test]
+    fn init_fails_when_manifest_exists() -> Result<()> {
+        Project::new()
+            .file(
+                "pkg/config.toml",
+                r#"
+                [package]
+                name = "pkg"
+                version = "0.1.0"
+                "#,
+            )
+            .command("init")
+            .arg("pkg")
+            .expected_output("error config.toml already exists in pkg")
+            .run()
+    }
+
+    #[test]
+    fn new_from_path_template() -> Result<()> {
+        let values = builtin_template_values("foo");
+        Project::new()
+            .file(
+                "tpl/template.toml",
+                r#"
+                [placeholders.license]
+                prompt = "License"
+                "#,
+            )
+            .file(
+                "tpl/README.md",
+                "# {{name}} ({{license}})\n\nCopyright {{year}} {{author}}",
+            )
+            .file(
+                "tpl/config.toml",
+                r#"
+                [package]
+                name = "{{name}}"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .command("new")
+            .arg("foo")
+            .arg("--template")
+            .arg("tpl")
+            .arg("-D")
+            .arg("license=MIT")
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", "user.name")
+            .env("GIT_CONFIG_VALUE_0", "Jane Doe")
+            .expected_output("info Created binary (application) `foo` package")
+            .expected_file(
+                "foo/README.md",
+                &format!("# foo (MIT)\n\nCopyright {} Jane Doe", values["year"]),
+            )
+            .expected_file(
+                "foo/config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .run()
+    }
+
+    #[test]
+    fn list_templates_with_descriptions() -> Result<()> {
+        Project::new()
+            .file(
+                "home/templates/web/template.toml",
+                r#"
+                [template]
+                description = "A web server"
+                "#,
+            )
+            .file("home/templates/bare/src/lib.zr", "")
+            .env("KELPIE_HOME", "{root}/home")
+            .command("new")
+            .arg("--list-templates")
+            .expected_success()
+            .expected_stdout(
+                "
+                Installed templates:
+                    bare
+                    web             A web server
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn list_templates_when_none_are_installed() -> Result<()> {
+        Project::new()
+            .env("KELPIE_HOME", "{root}/home")
+            .command("init")
+            .arg("--list-templates")
+            .expected_output("info no templates are installed in {root}/home/templates")
+            .expected_stdout("")
+            .run()
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod plugins_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn unknown_command_without_plugin_is_error() -> Result<()> {
+        Project::new()
+            .command("kelpie-test-missing-plugin")
+            .expected_output(
+                "error no such command `kelpie-test-missing-plugin`, see `kl --list` for the available commands",
+            )
+            .run()
+    }
+
+    /// A `kl-foo` plugin in the kelpie home that prints its arguments and environment. Its
+    /// arguments start with the `--test-logger` the tests add after the command.
+    fn with_plugin(project: Project) -> Project {
+        project
+            .executable(
+                "home/bin/kl-foo",
+                r#"#!/bin/sh
+                echo "args: $*"
+                echo "kl: $(basename "$KELPIE")"
+                echo "manifest: ${KELPIE_MANIFEST_PATH-unset}"
+                echo "root: ${KELPIE_WORKSPACE_ROOT-unset}"
+                "#,
+            )
+            .env("KELPIE_HOME", "{root}/home")
+            .command("foo")
+    }
+
+    #[test]
+    fn plugin_gets_the_arguments_and_the_project() -> Result<()> {
+        with_plugin(Project::new())
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["app"]
+                "#,
+            )
+            .file(
+                "app/config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [dependencies]
+                remote = { version = "0.1.0", git = "file:///kelpie-test-missing-repository" }
+                "#,
+            )
+            .file("app/src/lib.zr", "")
+            .arg("--release")
+            .arg("two words")
+            .expected_success()
+            .expected_stdout(
+                "
+                args: --test-logger --release two words
+                kl: cli
+                manifest: {root}/config.toml
+                root: {root}
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn plugin_outside_of_a_project() -> Result<()> {
+        with_plugin(Project::new())
+            .expected_success()
+            .expected_stdout(
+                "
+                args: --test-logger
+                kl: cli
+                manifest: unset
+                root: unset
+                ",
+            )
+            .run()
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod run_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn cannot_run_library() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("run")
+            .expected_output("error cannot run package foo because it is a library")
+            .run()
+    }
+
+    #[test]
+    fn workspace_with_several_binaries_needs_selection() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["a", "b"]
+                "#,
+            )
+            .file(
+                "a/config.toml",
+                r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .file("a/src/main.zr", "")
+            .file(
+                "b/config.toml",
+                r#"
+                [package]
+                name = "b"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .file("b/src/main.zr", "")
+            .command("run")
+            .expected_output(
+                "error could not determine which binary to run, use -p or --bin to pick one of: a, b",
+            )
+            .run()
+    }
+
+    #[test]
+    fn glob_matching_several_binaries_is_ambiguous() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["app-a", "app-b"]
+                "#,
+            )
+            .file(
+                "app-a/config.toml",
+                r#"
+                [package]
+                name = "app-a"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .file("app-a/src/main.zr", "")
+            .file(
+                "app-b/config.toml",
+                r#"
+                [package]
+                name = "app-b"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .file("app-b/src/main.zr", "")
+            .command("run")
+            .arg("-p")
+            .arg("app-*")
+            .expected_output(
+                "error could not determine which binary to run, use -p or --bin to pick one of: app-a, app-b",
+            )
+            .run()
+    }
+
+    #[test]
+    fn glob_matching_libraries_cannot_run() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["lib-a", "lib-b"]
+                "#,
+            )
+            .file(
+                "lib-a/config.toml",
+                r#"
+                [package]
+                name = "lib-a"
+                version = "0.1.0"
+                "#,
+            )
+            .file("lib-a/src/lib.zr", "")
+            .file(
+                "lib-b/config.toml",
+                r#"
+                [package]
+                name = "lib-b"
+                version = "0.1.0"
+                "#,
+            )
+            .file("lib-b/src/lib.zr", "")
+            .command("run")
+            .arg("-p")
+            .arg("lib*")
+            .expected_output("error cannot run package lib-a because it is a library")
+            .run()
+    }
+
+    /// Compiles the binary to a script printing its arguments and exiting with code 3.
+    #[cfg(unix)]
+    const SCRIPT_COMPILER: &str = r#"#!/bin/sh
+[ "$1" = "--version" ] && echo "zc 1.0" && exit 0
+name=$2
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --output) output=$2; shift 2 ;;
+        *) shift ;;
+    esac
+done
+mkdir -p "$output"
+printf '#!/bin/sh\necho "%s: $*"\nexit 3\n' "$name" > "$output/$name"
+chmod +x "$output/$name"
+"#;
+
+    #[cfg(unix)]
+    #[test]
+    fn run_forwards_arguments_and_exit_code() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                type = "bin"
+                "#,
+            )
+            .file("src/main.zr", "fn main() {}")
+            .executable("zc", SCRIPT_COMPILER)
+            .env("KELPIE_TOOLCHAIN_COMPILER", "{root}/zc")
+            .command("run")
+            .arg("--")
+            .arg("--verbose")
+            .arg("input.txt")
+            .expected_output("info Compiling foo v0.1.0")
+            .expected_stdout("foo: --verbose input.txt")
+            .expected_code(3)
+            .run()
+    }
+}
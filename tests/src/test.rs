@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod test_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn workspace_test_needs_selection() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["foo"]
+                "#,
+            )
+            .file(
+                "foo/config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("foo/src/lib.zr", "")
+            .command("test")
+            .expected_output(
+                "error Cannot test in a workspace without specifying a package to test (use -p <package-name> or --workspace)",
+            )
+            .run()
+    }
+
+    #[test]
+    fn test_rejects_unknown_package() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("test")
+            .arg("-p")
+            .arg("bar")
+            .expected_output("error Package 'bar' not found")
+            .run()
+    }
+
+    #[test]
+    fn shard_index_starts_at_one() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("test")
+            .arg("--shard")
+            .arg("0/2")
+            .expected_output(
+                "error: invalid value '0/2' for '--shard <INDEX/COUNT>': invalid shard `0/2`, expected <index>/<count> such as 1/3\n\nFor more information, try '--help'.",
+            )
+            .run()
+    }
+
+    /// Compiles test harnesses to a script running [`HARNESS`] on the test source, other
+    /// packages to an empty file.
+    #[cfg(unix)]
+    const HARNESS_COMPILER: &str = r#"#!/bin/sh
+[ "$1" = "--version" ] && echo "zc 1.0" && exit 0
+name=$2
+source=$3
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --output) output=$2; shift ;;
+        --test) test=1 ;;
+    esac
+    shift
+done
+mkdir -p "$output"
+if [ -n "$test" ]; then
+    printf '#!/bin/sh\nexec %s %s "$@"\n' "$(dirname "$0")/harness" "$source" > "$output/$name"
+    chmod +x "$output/$name"
+else
+    touch "$output/$name"
+fi
+"#;
+
+    /// A harness following the protocol of the compiler's: `--list` prints `<name>: test` or
+    /// `<name>: ignored` and `--exact <name>` runs one test. Sources hold a test per line, like
+    /// `pass adds`, `fail divides` or `ignore slow`.
+    #[cfg(unix)]
+    const HARNESS: &str = r#"#!/bin/sh
+source=$1
+shift
+if [ "$1" = "--list" ]; then
+    while read -r kind name; do
+        case "$kind" in
+            ignore) echo "$name: ignored" ;;
+            *) echo "$name: test" ;;
+        esac
+    done < "$source"
+    exit 0
+fi
+grep -q "^fail $2\$" "$source" && echo "$2 went wrong" && exit 1
+exit 0
+"#;
+
+    /// `foo` with unit tests in `src/lib.zr` and integration tests in `tests/api.zr`.
+    #[cfg(unix)]
+    fn harness_project(unit_tests: &str) -> Project {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", unit_tests)
+            .file("tests/api.zr", "pass api_works\nignore api_slow\n")
+            .executable("zc", HARNESS_COMPILER)
+            .executable("harness", HARNESS)
+            .env("KELPIE_TOOLCHAIN_COMPILER", "{root}/zc")
+            .command("test")
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_the_tests_listed_by_the_harnesses() -> Result<()> {
+        harness_project("pass adds\nignore slow\npass subtracts\n")
+            .expected_output(
+                "info Compiling foo v0.1.0\n\
+                 info Compiling foo v0.1.0 (unit tests)\n\
+                 info Compiling foo v0.1.0 (test \"api\")\n\
+                 info Running unit tests (target/release/tests/foo/foo)\n\
+                 info Running tests/api.zr (target/release/tests/foo/foo-api)\n\
+                 info foo: 3 passed, 0 failed, 2 ignored",
+            )
+            .expected_stdout(
+                "
+                test adds ... ok
+                test slow ... ignored
+                test subtracts ... ok
+                test api_works ... ok
+                test api_slow ... ignored
+                ",
+            )
+            .run()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filters_select_tests_by_name() -> Result<()> {
+        harness_project("pass adds\npass adds_twice\npass subtracts\n")
+            .arg("add")
+            .arg("api_works")
+            .expected_stdout(
+                "
+                test adds ... ok
+                test adds_twice ... ok
+                test api_works ... ok
+                ",
+            )
+            .run()?;
+
+        harness_project("pass adds\npass adds_twice\npass subtracts\n")
+            .arg("--exact")
+            .arg("adds")
+            .arg("add")
+            .expected_stdout("test adds ... ok")
+            .run()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_test_stops_at_its_harness() -> Result<()> {
+        harness_project("pass adds\nfail divides\n")
+            .expected_output(
+                "info Compiling foo v0.1.0\n\
+                 info Compiling foo v0.1.0 (unit tests)\n\
+                 info Compiling foo v0.1.0 (test \"api\")\n\
+                 info Running unit tests (target/release/tests/foo/foo)\n\
+                 info foo: 1 passed, 1 failed, 0 ignored\n\
+                 error tests of package foo failed",
+            )
+            .expected_stdout(
+                "
+                test adds ... ok
+                test divides ... FAILED
+
+                ---- divides output ----
+                divides went wrong
+                ",
+            )
+            .expected_code(1)
+            .run()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn no_fail_fast_runs_the_harnesses_after_a_failure() -> Result<()> {
+        harness_project("pass adds\nfail divides\n")
+            .arg("--no-fail-fast")
+            .expected_output(
+                "info Compiling foo v0.1.0\n\
+                 info Compiling foo v0.1.0 (unit tests)\n\
+                 info Compiling foo v0.1.0 (test \"api\")\n\
+                 info Running unit tests (target/release/tests/foo/foo)\n\
+                 info Running tests/api.zr (target/release/tests/foo/foo-api)\n\
+                 info foo: 2 passed, 1 failed, 1 ignored\n\
+                 error tests of package foo failed",
+            )
+            .expected_stdout(
+                "
+                test adds ... ok
+                test divides ... FAILED
+
+                ---- divides output ----
+                divides went wrong
+                test api_works ... ok
+                test api_slow ... ignored
+                ",
+            )
+            .expected_code(1)
+            .run()
+    }
+}
@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod tree_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    fn package(name: &str, version: &str, dependencies: &[(&str, &str, &str)]) -> String {
+        let mut manifest =
+            format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n\n[dependencies]\n");
+        for (name, version, path) in dependencies {
+            manifest.push_str(&format!(
+                "{name} = {{ version = \"{version}\", path = \"{path}\" }}\n"
+            ));
+        }
+        manifest
+    }
+
+    /// `app` uses `fmt` through a normal, a dev- and a build-dependency, and two versions of
+    /// `log`.
+    fn project() -> Project {
+        let mut project = Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [dependencies]
+                log = { version = "0.1.0", path = "log" }
+                net = { version = "0.1.0", path = "net" }
+
+                [dev-dependencies]
+                check = { version = "0.1.0", path = "check" }
+
+                [build-dependencies]
+                gen = { version = "0.1.0", path = "gen" }
+                "#,
+            )
+            .file("src/lib.zr", "");
+
+        for (dir, name, version, dependencies) in [
+            ("log", "log", "0.1.0", &[("fmt", "0.1.0", "../fmt")][..]),
+            ("log2", "log", "0.2.0", &[]),
+            ("net", "net", "0.1.0", &[("log", "0.2.0", "../log2")]),
+            ("fmt", "fmt", "0.1.0", &[("util", "0.1.0", "../util")]),
+            ("util", "util", "0.1.0", &[]),
+            ("check", "check", "0.1.0", &[("fmt", "0.1.0", "../fmt")]),
+            ("gen", "gen", "0.1.0", &[("fmt", "0.1.0", "../fmt")]),
+        ] {
+            project = project
+                .file(
+                    &format!("{dir}/config.toml"),
+                    &package(name, version, dependencies),
+                )
+                .file(&format!("{dir}/src/lib.zr"), "");
+        }
+        project
+    }
+
+    #[test]
+    fn expanded_packages_are_marked() -> Result<()> {
+        project()
+            .command("tree")
+            .expected_stdout(
+                "
+                app v0.1.0
+                ├── log v0.1.0
+                │   └── fmt v0.1.0
+                │       └── util v0.1.0
+                └── net v0.1.0
+                    └── log v0.2.0
+                [dev-dependencies]
+                └── check v0.1.0
+                    └── fmt v0.1.0 (*)
+                [build-dependencies]
+                └── gen v0.1.0
+                    └── fmt v0.1.0 (*)
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn depth_limits_the_tree() -> Result<()> {
+        project()
+            .command("tree")
+            .arg("--depth")
+            .arg("1")
+            .expected_stdout(
+                "
+                app v0.1.0
+                ├── log v0.1.0
+                └── net v0.1.0
+                [dev-dependencies]
+                └── check v0.1.0
+                [build-dependencies]
+                └── gen v0.1.0
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn edge_kinds_and_depth_prefix() -> Result<()> {
+        project()
+            .command("tree")
+            .arg("-e")
+            .arg("normal")
+            .arg("--prefix")
+            .arg("depth")
+            .expected_stdout(
+                "
+                0app v0.1.0
+                1log v0.1.0
+                2fmt v0.1.0
+                3util v0.1.0
+                1net v0.1.0
+                2log v0.2.0
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn no_prefix() -> Result<()> {
+        project()
+            .command("tree")
+            .arg("-e")
+            .arg("build,dev")
+            .arg("--prefix")
+            .arg("none")
+            .expected_stdout(
+                "
+                app v0.1.0
+                check v0.1.0
+                gen v0.1.0
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn invert_shows_dependents() -> Result<()> {
+        project()
+            .command("tree")
+            .arg("-i")
+            .arg("util")
+            .expected_stdout(
+                "
+                util v0.1.0
+                └── fmt v0.1.0
+                    ├── check v0.1.0
+                    │   [dev-dependencies]
+                    │   └── app v0.1.0
+                    ├── gen v0.1.0
+                    │   [build-dependencies]
+                    │   └── app v0.1.0
+                    └── log v0.1.0
+                        └── app v0.1.0
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn duplicates_show_every_version() -> Result<()> {
+        project()
+            .command("tree")
+            .arg("-d")
+            .expected_stdout(
+                "
+                log v0.1.0
+                └── app v0.1.0
+
+                log v0.2.0
+                └── net v0.1.0
+                    └── app v0.1.0
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn invert_rejects_unknown_package() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("tree")
+            .arg("-i")
+            .arg("bar")
+            .expected_output("error Package 'bar' not found in the dependency tree")
+            .run()
+    }
+
+    #[test]
+    fn rejects_unknown_edge_kind() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("tree")
+            .arg("-e")
+            .arg("normal,optional")
+            .expected_output(
+                "error: invalid value 'optional' for '--edges <KINDS>'\n  [possible values: normal, dev, build]\n\nFor more information, try '--help'.",
+            )
+            .run()
+    }
+}
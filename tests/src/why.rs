@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod why_tests {
+    use anyhow::Result;
+    use test_lib::Project;
+
+    #[test]
+    fn why_rejects_unknown_package() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .command("why")
+            .arg("bar")
+            .expected_output("error Package 'bar' not found in the dependency tree")
+            .run()
+    }
+
+    #[test]
+    fn why_notes_workspace_members() -> Result<()> {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [workspace]
+                members = ["foo", "bar"]
+                "#,
+            )
+            .file(
+                "foo/config.toml",
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                type = "bin"
+
+                [dependencies]
+                bar = { version = "0.1.0", path = "../bar" }
+                "#,
+            )
+            .file("foo/src/main.zr", "")
+            .file(
+                "bar/config.toml",
+                r#"
+                [package]
+                name = "bar"
+                version = "0.1.0"
+                "#,
+            )
+            .file("bar/src/lib.zr", "")
+            .command("why")
+            .arg("bar@0.1")
+            .expected_success()
+            .expected_output("info bar v0.1.0 is a workspace member")
+            .expected_stdout("bar v0.1.0\n    foo v0.1.0 -> bar v0.1.0")
+            .run()
+    }
+
+    fn package(name: &str, version: &str, dependencies: &str) -> String {
+        format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n\n{dependencies}")
+    }
+
+    /// `app` reaches `fmt` through a normal, a dev- and a build-dependency, and uses two
+    /// versions of `log`.
+    fn project() -> Project {
+        Project::new()
+            .file(
+                "config.toml",
+                r#"
+                [package]
+                name = "app"
+                version = "0.1.0"
+
+                [dependencies]
+                log = { version = "0.1.0", path = "log" }
+                net = { version = "0.1.0", path = "net" }
+
+                [dev-dependencies]
+                check = { version = "0.1.0", path = "check" }
+
+                [build-dependencies]
+                gen = { version = "0.1.0", path = "gen" }
+                "#,
+            )
+            .file("src/lib.zr", "")
+            .file(
+                "log/config.toml",
+                &package(
+                    "log",
+                    "0.1.0",
+                    "[dependencies]\nfmt = { version = \"0.1.0\", path = \"../fmt\" }",
+                ),
+            )
+            .file("log/src/lib.zr", "")
+            .file("log2/config.toml", &package("log", "0.2.0", ""))
+            .file("log2/src/lib.zr", "")
+            .file(
+                "net/config.toml",
+                &package(
+                    "net",
+                    "0.1.0",
+                    "[dependencies]\nlog = { version = \"0.2.0\", path = \"../log2\" }",
+                ),
+            )
+            .file("net/src/lib.zr", "")
+            .file(
+                "fmt/config.toml",
+                &package("fmt", "0.1.0", "[features]\ncolor = []"),
+            )
+            .file("fmt/src/lib.zr", "")
+            .file(
+                "check/config.toml",
+                &package(
+                    "check",
+                    "0.1.0",
+                    "[dependencies]\nfmt = { version = \"0.1.0\", path = \"../fmt\", features = [\"color\"] }",
+                ),
+            )
+            .file("check/src/lib.zr", "")
+            .file(
+                "gen/config.toml",
+                &package(
+                    "gen",
+                    "0.1.0",
+                    "[dependencies]\nfmt = { version = \"0.1.0\", path = \"../fmt\" }",
+                ),
+            )
+            .file("gen/src/lib.zr", "")
+    }
+
+    #[test]
+    fn why_prints_every_shortest_path_and_why_it_is_taken() -> Result<()> {
+        project()
+            .command("why")
+            .arg("fmt")
+            .expected_success()
+            .expected_stdout(
+                "
+                fmt v0.1.0
+                    app v0.1.0 -> check v0.1.0 [dev] -> fmt v0.1.0 [features: color]
+                    app v0.1.0 -> gen v0.1.0 [build] -> fmt v0.1.0
+                    app v0.1.0 -> log v0.1.0 -> fmt v0.1.0
+                ",
+            )
+            .run()
+    }
+
+    #[test]
+    fn why_explains_every_version() -> Result<()> {
+        project()
+            .command("why")
+            .arg("log")
+            .expected_success()
+            .expected_stdout(
+                "
+                log v0.1.0
+                    app v0.1.0 -> log v0.1.0
+
+                log v0.2.0
+                    app v0.1.0 -> net v0.1.0 -> log v0.2.0
+                ",
+            )
+            .run()
+    }
+}